@@ -0,0 +1,860 @@
+pub(crate) mod theme;
+pub use theme::Theme;
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+use toml::{value::Table, Value};
+
+use crate::error::{Error, Result};
+use crate::style::{
+    BlockQuoteStyle, CodeBlockStyle, CodeStyle, FootnoteStyle, HeadingStyle, LinkMode, LinkStyle,
+    LongLines, OrderedListStyle, ParagraphStyle, RuleStyle, StrikethroughStyle, StyleSetBuilder,
+    TableStyle, TaskListStyle, UnorderedListStyle,
+};
+
+/// The settings every config file is merged over.
+const DEFAULT_CONFIG: &str = include_str!("default.toml");
+
+/// The name of the config file of a project, merged over the user config.
+const PROJECT_CONFIG: &str = ".dear-md.toml";
+
+const STYLE_KEYS: [&str; 3] = ["color", "background", "attributes"];
+
+const HEADING_LEVELS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Settings read from TOML config files merged over each other, falling back to the
+/// built-in defaults for everything the files leave out.
+#[derive(Clone)]
+pub struct Config {
+    /// The settings of the config files, merged over each other.
+    overrides: Table,
+    override_sources: BTreeMap<String, String>,
+    /// The settings of the config files merged over their theme and the defaults.
+    table: Table,
+    /// Where each value of `table` comes from, by dotted key, for values that are not defaults.
+    sources: BTreeMap<String, String>,
+    /// Where theme files are looked for, besides the built-in themes.
+    theme_dir: Option<PathBuf>,
+    theme: String,
+    width: Option<usize>,
+    link_mode: Option<LinkMode>,
+    pager: bool,
+    case_sensitive: bool,
+    code_block: CodeBlockConfig,
+    styles: Styles,
+}
+
+#[derive(Clone)]
+struct CodeBlockConfig {
+    tab_width: usize,
+    long_lines: LongLines,
+    frame: bool,
+    continuation_glyph: String,
+    ellipsis_glyph: String,
+}
+
+/// The styles and glyphs of every [`StyleSet`](crate::StyleSet) member.
+#[derive(Clone)]
+struct Styles {
+    paragraph: ParagraphStyle,
+    heading: Vec<HeadingStyle>,
+    unordered_list: UnorderedListStyle,
+    ordered_list: OrderedListStyle,
+    task_list: TaskListStyle,
+    block_quote: BlockQuoteStyle,
+    code: CodeStyle,
+    link: LinkStyle,
+    strikethrough: StrikethroughStyle,
+    table: TableStyle,
+    footnote: FootnoteStyle,
+    rule: RuleStyle,
+}
+
+impl Config {
+    /// Where the config file is looked for when none is given:
+    /// `$XDG_CONFIG_HOME/dear-md/config.toml`, or `~/.config/dear-md/config.toml`.
+    pub fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("config.toml"))
+    }
+
+    /// The `.dear-md.toml` closest to `dir`, looked for in `dir` and then in its parents up
+    /// to the root of the git repository it is in. Outside of a repository, only `dir` is
+    /// looked in.
+    pub fn project_path(dir: &Path) -> Option<PathBuf> {
+        let dir = dir.canonicalize().ok()?;
+        let root = dir.ancestors().find(|dir| dir.join(".git").exists());
+        let mut dirs = dir.ancestors();
+        match root {
+            Some(root) => dirs.find(|dir| dir.join(PROJECT_CONFIG).is_file() || *dir == root),
+            None => dirs.next(),
+        }
+        .map(|dir| dir.join(PROJECT_CONFIG))
+        .filter(|path| path.is_file())
+    }
+
+    /// Merges the config file at `path` over this config, failing on unknown keys and
+    /// invalid values.
+    pub fn merge_file(self, path: &Path) -> Result<Self> {
+        let overrides: Table = toml::from_str(&fs::read_to_string(path)?)?;
+        let source = path.display().to_string();
+        self.merge(overrides, &source)
+    }
+
+    /// Uses the styles of the theme called `name`, see [`Theme`], instead of the one the
+    /// config files pick.
+    pub fn style_theme(self, name: &str) -> Result<Self> {
+        let mut overrides = Table::new();
+        overrides.insert("style_theme".to_owned(), Value::String(name.to_owned()));
+        self.merge(overrides, "override")
+    }
+
+    /// Looks for theme files in `dir`, usually [`Theme::dir`], which win over the built-in
+    /// themes of the same name. Without it, only the built-in themes are available.
+    pub fn theme_dir(self, dir: &Path) -> Result<Self> {
+        Config::resolve(self.overrides, self.override_sources, Some(dir.to_owned()))
+    }
+
+    fn merge(self, overrides: Table, source: &str) -> Result<Self> {
+        let mut override_sources = self.override_sources;
+        let mut merged = self.overrides;
+        merge(&mut merged, overrides, "", source, &mut override_sources);
+        Config::resolve(merged, override_sources, self.theme_dir)
+    }
+
+    /// Merges the settings of the config files over their theme and the defaults.
+    fn resolve(
+        overrides: Table,
+        override_sources: BTreeMap<String, String>,
+        theme_dir: Option<PathBuf>,
+    ) -> Result<Self> {
+        let mut table = default_table();
+        let name = match Section::root(&overrides).string("style_theme")? {
+            Some(name) => name,
+            None => Section::root(&table)
+                .string("style_theme")?
+                .unwrap_or_default(),
+        };
+        let theme = Theme::load_from(&name, theme_dir.as_deref())?;
+
+        let mut sources = BTreeMap::new();
+        let source = format!("theme {}", theme.name());
+        merge(&mut table, theme.table().clone(), "", &source, &mut sources);
+        merge(&mut table, overrides.clone(), "", "", &mut BTreeMap::new());
+        for (key, source) in &override_sources {
+            sources.retain(|theme_key, _| !theme_key.starts_with(&format!("{}.", key)));
+            sources.insert(key.clone(), source.clone());
+        }
+        Config::from_table(table, sources, overrides, override_sources, theme_dir)
+    }
+
+    fn from_table(
+        table: Table,
+        sources: BTreeMap<String, String>,
+        overrides: Table,
+        override_sources: BTreeMap<String, String>,
+        theme_dir: Option<PathBuf>,
+    ) -> Result<Self> {
+        let root = Section::root(&table);
+        root.check_keys(&[
+            "style_theme",
+            "theme",
+            "width",
+            "links",
+            "pager",
+            "code_block",
+            "style",
+        ])?;
+
+        let link_mode = match root.string("links")?.as_deref() {
+            None | Some("auto") => None,
+            Some("hyperlink") => Some(LinkMode::Hyperlink),
+            Some("inline") => Some(LinkMode::Inline),
+            Some("references") => Some(LinkMode::References),
+            Some(links) => {
+                return Err(root.invalid(
+                    "links",
+                    format!(
+                        "unknown link mode `{}`, expected auto, hyperlink, inline or references",
+                        links
+                    ),
+                ))
+            }
+        };
+
+        let pager = root.section("pager")?;
+        pager.check_keys(&["enabled", "case_sensitive"])?;
+
+        Ok(Self {
+            theme: root.string("theme")?.unwrap_or_default(),
+            width: root.integer("width")?,
+            link_mode,
+            pager: pager.boolean("enabled")?.unwrap_or_default(),
+            case_sensitive: pager.boolean("case_sensitive")?.unwrap_or_default(),
+            code_block: CodeBlockConfig::from_section(&root.section("code_block")?)?,
+            styles: Styles::from_section(&root.section("style")?)?,
+            overrides,
+            override_sources,
+            table,
+            sources,
+            theme_dir,
+        })
+    }
+
+    /// The syntax highlighting theme of code blocks.
+    pub fn theme(&self) -> &str {
+        &self.theme
+    }
+
+    pub fn width(&self) -> Option<usize> {
+        self.width
+    }
+
+    /// How links are shown, or `None` to pick a mode depending on the terminal.
+    pub fn link_mode(&self) -> Option<LinkMode> {
+        self.link_mode
+    }
+
+    /// Whether output to a terminal goes through the pager.
+    pub fn pager(&self) -> bool {
+        self.pager
+    }
+
+    /// Whether pager searches match the case of the pattern.
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// A code block style `width` columns wide using `theme`.
+    pub fn code_block_style(&self, width: usize, theme: &str) -> Result<CodeBlockStyle> {
+        let code_block = &self.code_block;
+        Ok(CodeBlockStyle::new(width, theme)?
+            .tab_width(code_block.tab_width)
+            .long_lines(code_block.long_lines)
+            .frame(code_block.frame)
+            .continuation_glyph(&code_block.continuation_glyph)
+            .ellipsis_glyph(&code_block.ellipsis_glyph))
+    }
+
+    /// A builder with the configured styles of everything but code blocks.
+    pub fn style_set_builder(&self) -> StyleSetBuilder {
+        self.styles.apply(StyleSetBuilder::new())
+    }
+}
+
+impl Styles {
+    /// Sets these styles on `builder`.
+    fn apply(&self, builder: StyleSetBuilder) -> StyleSetBuilder {
+        let styles = self.clone();
+        builder
+            .paragraph(styles.paragraph)
+            .heading(styles.heading)
+            .unordered_list(styles.unordered_list)
+            .ordered_list(styles.ordered_list)
+            .task_list(styles.task_list)
+            .block_quote(styles.block_quote)
+            .code(styles.code)
+            .link(styles.link)
+            .strikethrough(styles.strikethrough)
+            .table(styles.table)
+            .footnote(styles.footnote)
+            .rule(styles.rule)
+    }
+}
+
+impl CodeBlockConfig {
+    fn from_section(section: &Section) -> Result<Self> {
+        section.check_keys(&[
+            "tab_width",
+            "long_lines",
+            "frame",
+            "continuation_glyph",
+            "ellipsis_glyph",
+        ])?;
+        let long_lines = match section.string("long_lines")?.as_deref() {
+            Some("wrap") | None => LongLines::Wrap,
+            Some("truncate") => LongLines::Truncate,
+            Some("widen") => LongLines::Widen,
+            Some(long_lines) => {
+                return Err(section.invalid(
+                    "long_lines",
+                    format!(
+                        "unknown value `{}`, expected wrap, truncate or widen",
+                        long_lines
+                    ),
+                ))
+            }
+        };
+        Ok(Self {
+            tab_width: section.integer("tab_width")?.unwrap_or(4),
+            long_lines,
+            frame: section.boolean("frame")?.unwrap_or_default(),
+            continuation_glyph: section.string("continuation_glyph")?.unwrap_or_default(),
+            ellipsis_glyph: section.string("ellipsis_glyph")?.unwrap_or_default(),
+        })
+    }
+}
+
+impl Styles {
+    fn from_section(section: &Section) -> Result<Self> {
+        section.check_keys(&[
+            "paragraph",
+            "heading",
+            "unordered_list",
+            "ordered_list",
+            "task_list",
+            "block_quote",
+            "code",
+            "link",
+            "strikethrough",
+            "table",
+            "footnote",
+            "rule",
+        ])?;
+
+        let heading = section.section("heading")?;
+        heading.check_keys(&[&STYLE_KEYS[..], &HEADING_LEVELS[..]].concat())?;
+        let heading_style = heading.style(ContentStyle::new())?;
+        let heading = HEADING_LEVELS
+            .iter()
+            .map(|level| {
+                let level = heading.section(level)?;
+                level.check_keys(&STYLE_KEYS)?;
+                Ok(HeadingStyle::new(level.style(heading_style)?))
+            })
+            .collect::<Result<_>>()?;
+
+        let task_list = section.section("task_list")?;
+        task_list.check_keys(
+            &[
+                &STYLE_KEYS[..],
+                &["unchecked_glyph", "checked_glyph", "checked"],
+            ]
+            .concat(),
+        )?;
+        let checked = task_list.section("checked")?;
+        checked.check_keys(&STYLE_KEYS)?;
+
+        let link = section.section("link")?;
+        link.check_keys(&[&STYLE_KEYS[..], &["url"]].concat())?;
+        let url = link.section("url")?;
+        url.check_keys(&STYLE_KEYS)?;
+
+        let strikethrough = section.section("strikethrough")?;
+        strikethrough.check_keys(&["attribute"])?;
+        let attribute = match strikethrough.string("attribute")? {
+            Some(attribute) => parse_attribute(&attribute)
+                .ok_or_else(|| strikethrough.invalid("attribute", unknown_attribute(&attribute)))?,
+            None => Attribute::CrossedOut,
+        };
+
+        let table = section.section("table")?;
+        table.check_keys(&["border", "header", "cell"])?;
+        let table_style = |key: &str| -> Result<ContentStyle> {
+            let part = table.section(key)?;
+            part.check_keys(&STYLE_KEYS)?;
+            part.style(ContentStyle::new())
+        };
+
+        let (unordered_list, unordered_glyph) = section.glyph_style("unordered_list")?;
+        let (ordered_list, ordered_glyph) = section.glyph_style("ordered_list")?;
+        let (block_quote, block_quote_glyph) = section.glyph_style("block_quote")?;
+        let (footnote, footnote_glyph) = section.glyph_style("footnote")?;
+        let (rule, rule_glyph) = section.glyph_style("rule")?;
+
+        Ok(Self {
+            paragraph: ParagraphStyle::new(section.plain_style("paragraph")?),
+            heading,
+            unordered_list: UnorderedListStyle::new(unordered_list, &unordered_glyph),
+            ordered_list: OrderedListStyle::new(ordered_list, &ordered_glyph),
+            task_list: TaskListStyle::new(
+                task_list.style(ContentStyle::new())?,
+                checked.style(ContentStyle::new())?,
+                &task_list.string("unchecked_glyph")?.unwrap_or_default(),
+                &task_list.string("checked_glyph")?.unwrap_or_default(),
+            ),
+            block_quote: BlockQuoteStyle::new(block_quote, &block_quote_glyph),
+            code: CodeStyle::new(section.plain_style("code")?),
+            link: LinkStyle::new(
+                link.style(ContentStyle::new())?,
+                url.style(ContentStyle::new())?,
+            ),
+            strikethrough: StrikethroughStyle::new(attribute),
+            table: TableStyle::new(
+                table_style("border")?,
+                table_style("header")?,
+                table_style("cell")?,
+            ),
+            footnote: FootnoteStyle::new(footnote, &footnote_glyph),
+            rule: RuleStyle::new(rule, &rule_glyph),
+        })
+    }
+}
+
+/// A table of the config and the dotted key it is found at, for error messages.
+struct Section<'a> {
+    path: String,
+    table: &'a Table,
+}
+
+impl<'a> Section<'a> {
+    fn root(table: &'a Table) -> Self {
+        Self {
+            path: String::new(),
+            table,
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        dotted_key(&self.path, key)
+    }
+
+    fn invalid(&self, key: &str, message: impl Into<String>) -> Error {
+        Error::Config {
+            key: self.key(key),
+            message: message.into(),
+        }
+    }
+
+    fn check_keys(&self, keys: &[&str]) -> Result<()> {
+        match self.table.keys().find(|key| !keys.contains(&key.as_str())) {
+            Some(key) => Err(self.invalid(key, "unknown key")),
+            None => Ok(()),
+        }
+    }
+
+    /// The table at `key`, empty when it is missing.
+    fn section(&self, key: &str) -> Result<Section<'a>> {
+        static EMPTY: OnceLock<Table> = OnceLock::new();
+        let table = match self.table.get(key) {
+            Some(Value::Table(table)) => table,
+            Some(_) => return Err(self.invalid(key, "expected a table")),
+            None => EMPTY.get_or_init(Table::new),
+        };
+        Ok(Section {
+            path: self.key(key),
+            table,
+        })
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>> {
+        match self.table.get(key) {
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(self.invalid(key, "expected a string")),
+            None => Ok(None),
+        }
+    }
+
+    fn boolean(&self, key: &str) -> Result<Option<bool>> {
+        match self.table.get(key) {
+            Some(Value::Boolean(value)) => Ok(Some(*value)),
+            Some(_) => Err(self.invalid(key, "expected true or false")),
+            None => Ok(None),
+        }
+    }
+
+    fn integer(&self, key: &str) -> Result<Option<usize>> {
+        match self.table.get(key) {
+            Some(Value::Integer(value)) if *value > 0 => Ok(Some(*value as usize)),
+            Some(_) => Err(self.invalid(key, "expected a positive integer")),
+            None => Ok(None),
+        }
+    }
+
+    /// Applies the `color`, `background` and `attributes` of the section to `style`.
+    fn style(&self, mut style: ContentStyle) -> Result<ContentStyle> {
+        if let Some(color) = self.string("color")? {
+            style.foreground_color =
+                parse_color(&color).map_err(|message| self.invalid("color", message))?;
+        }
+        if let Some(color) = self.string("background")? {
+            style.background_color =
+                parse_color(&color).map_err(|message| self.invalid("background", message))?;
+        }
+        match self.table.get("attributes") {
+            Some(Value::Array(attributes)) => {
+                style.attributes = Default::default();
+                for attribute in attributes {
+                    let attribute = match attribute {
+                        Value::String(attribute) => parse_attribute(attribute).ok_or_else(|| {
+                            self.invalid("attributes", unknown_attribute(attribute))
+                        }),
+                        _ => Err(self.invalid("attributes", "expected a list of strings")),
+                    }?;
+                    style = style.attribute(attribute);
+                }
+            }
+            Some(_) => return Err(self.invalid("attributes", "expected a list of strings")),
+            None => (),
+        }
+        Ok(style)
+    }
+
+    /// The style of a member that only has style keys.
+    fn plain_style(&self, key: &str) -> Result<ContentStyle> {
+        let section = self.section(key)?;
+        section.check_keys(&STYLE_KEYS)?;
+        section.style(ContentStyle::new())
+    }
+
+    /// The style and glyph of a member that draws a glyph.
+    fn glyph_style(&self, key: &str) -> Result<(ContentStyle, String)> {
+        let section = self.section(key)?;
+        section.check_keys(&[&STYLE_KEYS[..], &["glyph"]].concat())?;
+        Ok((
+            section.style(ContentStyle::new())?,
+            section.string("glyph")?.unwrap_or_default(),
+        ))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/dear-md`, or `~/.config/dear-md`.
+fn config_dir() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("dear-md"))
+}
+
+fn default_table() -> Table {
+    toml::from_str(DEFAULT_CONFIG).expect("the default config is valid TOML")
+}
+
+/// Merges `overrides` read from `source` into `table`, key by key in nested tables, and
+/// records the dotted key of each value it sets in `sources`.
+fn merge(
+    table: &mut Table,
+    overrides: Table,
+    path: &str,
+    source: &str,
+    sources: &mut BTreeMap<String, String>,
+) {
+    for (key, value) in overrides {
+        let dotted = dotted_key(path, &key);
+        match (table.get_mut(&key), value) {
+            (Some(Value::Table(table)), Value::Table(overrides)) => {
+                merge(table, overrides, &dotted, source, sources)
+            }
+            (_, Value::Table(overrides)) => {
+                let mut new_table = Table::new();
+                merge(&mut new_table, overrides, &dotted, source, sources);
+                table.insert(key, Value::Table(new_table));
+            }
+            (_, value) => {
+                sources.retain(|key, _| !key.starts_with(&format!("{}.", dotted)));
+                sources.insert(dotted, source.to_owned());
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+fn dotted_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Parses a color name such as `dark_grey`, a `#rrggbb` color, an ANSI color number or
+/// `none`.
+fn parse_color(color: &str) -> std::result::Result<Option<Color>, String> {
+    if color == "none" {
+        return Ok(None);
+    }
+    if let Some(hex) = color.strip_prefix('#') {
+        let channel = |index: usize| {
+            hex.get(index..index + 2)
+                .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Some(Color::Rgb { r, g, b })),
+            _ => Err(format!("invalid color `{}`, expected #rrggbb", color)),
+        };
+    }
+    if let Ok(value) = color.parse::<u8>() {
+        return Ok(Some(Color::AnsiValue(value)));
+    }
+    Color::try_from(color).map(Some).map_err(|_| {
+        format!(
+            "unknown color `{}`, expected a color name such as dark_grey, #rrggbb, \
+             an ANSI color number or none",
+            color
+        )
+    })
+}
+
+fn parse_attribute(attribute: &str) -> Option<Attribute> {
+    let attribute = match attribute {
+        "bold" => Attribute::Bold,
+        "dim" => Attribute::Dim,
+        "italic" => Attribute::Italic,
+        "underlined" => Attribute::Underlined,
+        "slow_blink" => Attribute::SlowBlink,
+        "rapid_blink" => Attribute::RapidBlink,
+        "reverse" => Attribute::Reverse,
+        "hidden" => Attribute::Hidden,
+        "crossed_out" => Attribute::CrossedOut,
+        "overlined" => Attribute::OverLined,
+        _ => return None,
+    };
+    Some(attribute)
+}
+
+fn unknown_attribute(attribute: &str) -> String {
+    format!(
+        "unknown attribute `{}`, expected bold, dim, italic, underlined, reverse, \
+         crossed_out or another crossterm attribute",
+        attribute
+    )
+}
+
+impl Config {
+    /// Writes the values of `table` and then its nested tables, each value followed by the
+    /// file it comes from.
+    fn write_table(&self, f: &mut fmt::Formatter<'_>, table: &Table, path: &str) -> fmt::Result {
+        let values: Vec<(String, String)> = table
+            .iter()
+            .filter(|(_, value)| !value.is_table())
+            .map(|(key, value)| (dotted_key(path, key), format!("{} = {}", key, value)))
+            .collect();
+        if !values.is_empty() {
+            if !path.is_empty() {
+                writeln!(f, "\n[{}]", path)?;
+            }
+            let width = values
+                .iter()
+                .map(|(_, line)| line.chars().count())
+                .max()
+                .unwrap_or(0);
+            for (key, line) in values {
+                let source = self.sources.get(&key).map_or("default", String::as_str);
+                writeln!(f, "{:<2$}  # {}", line, source, width)?;
+            }
+        }
+        for (key, value) in table {
+            if let Value::Table(table) = value {
+                self.write_table(f, table, &dotted_key(path, key))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the merged config as TOML, with the file each value comes from in a comment.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_table(f, &self.table, "")
+    }
+}
+
+/// The built-in defaults, with the built-in themes only.
+impl Default for Config {
+    fn default() -> Self {
+        Config::resolve(Table::new(), BTreeMap::new(), None).expect("the default config is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> Table {
+        toml::from_str(toml).expect("valid TOML")
+    }
+
+    fn merged(toml: &str) -> Result<Config> {
+        Config::default().merge(table(toml), "test.toml")
+    }
+
+    /// The dotted key an invalid config is rejected at.
+    fn error_key(toml: &str) -> String {
+        match merged(toml).err() {
+            Some(Error::Config { key, .. }) => key,
+            Some(err) => panic!("unexpected error for {:?}: {}", toml, err),
+            None => panic!("no error for {:?}", toml),
+        }
+    }
+
+    #[test]
+    fn merge_overrides_nested_keys_only() {
+        let mut merged = table("a = 1\n[b]\nc = 2\nd = 3\n[b.e]\nf = 4");
+        let mut sources = BTreeMap::new();
+        merge(
+            &mut merged,
+            table("[b]\nd = 5\n[b.e]\ng = 6\n[h]\ni = 7"),
+            "",
+            "x.toml",
+            &mut sources,
+        );
+        assert_eq!(
+            merged,
+            table("a = 1\n[b]\nc = 2\nd = 5\n[b.e]\nf = 4\ng = 6\n[h]\ni = 7")
+        );
+    }
+
+    #[test]
+    fn merge_records_where_values_come_from() {
+        let mut merged = table("a = 1\n[b]\nc = 2");
+        let mut sources = BTreeMap::new();
+        merge(
+            &mut merged,
+            table("[b]\nc = 3\nd = 4"),
+            "",
+            "x.toml",
+            &mut sources,
+        );
+        merge(
+            &mut merged,
+            table("a = 5\n[b]\nd = 6"),
+            "",
+            "y.toml",
+            &mut sources,
+        );
+        let expected = [("a", "y.toml"), ("b.c", "x.toml"), ("b.d", "y.toml")];
+        assert_eq!(
+            sources,
+            expected
+                .iter()
+                .map(|(key, source)| (key.to_string(), source.to_string()))
+                .collect()
+        );
+
+        // A value replacing a table takes over the keys of the table.
+        merge(&mut merged, table("b = 7"), "", "z.toml", &mut sources);
+        assert_eq!(sources.get("b").map(String::as_str), Some("z.toml"));
+        assert!(!sources.contains_key("b.c"));
+        assert!(!sources.contains_key("b.d"));
+    }
+
+    #[test]
+    fn printed_config_names_sources() {
+        let config = merged("width = 60\n[style.heading.h2]\ncolor = \"red\"")
+            .and_then(|config| config.merge(table("width = 70"), "other.toml"))
+            .expect("valid config");
+        let printed = config.to_string();
+        let line = |start: &str| {
+            printed
+                .lines()
+                .find(|line| line.starts_with(start))
+                .unwrap_or_else(|| panic!("no `{}` in\n{}", start, printed))
+                .to_owned()
+        };
+        assert!(line("width = 70").ends_with("# other.toml"));
+        assert!(line("color = \"red\"").ends_with("# test.toml"));
+        assert!(line("tab_width = 4").ends_with("# default"));
+        assert!(printed.contains("\n[style.heading.h2]\n"));
+    }
+
+    #[test]
+    fn later_files_win() {
+        let config = merged("width = 60\nlinks = \"inline\"")
+            .and_then(|config| config.merge(table("width = 90"), "other.toml"))
+            .expect("valid config");
+        assert_eq!(config.width(), Some(90));
+        assert_eq!(config.link_mode(), Some(LinkMode::Inline));
+    }
+
+    #[test]
+    fn unknown_keys_are_named() {
+        let cases = [
+            ("colour = \"red\"", "colour"),
+            ("[pager]\nenable = true", "pager.enable"),
+            ("[style.heading]\ncolour = \"red\"", "style.heading.colour"),
+            ("[style.heading.h7]\ncolor = \"red\"", "style.heading.h7"),
+            (
+                "[style.heading.h2]\nglyph = \"#\"",
+                "style.heading.h2.glyph",
+            ),
+            ("[style.paragraf]\ncolor = \"red\"", "style.paragraf"),
+        ];
+        for (toml, key) in cases {
+            assert_eq!(error_key(toml), key, "error of {:?}", toml);
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_named() {
+        let cases = [
+            ("width = 0", "width"),
+            ("links = \"never\"", "links"),
+            ("[pager]\nenabled = \"yes\"", "pager.enabled"),
+            (
+                "[code_block]\nlong_lines = \"fold\"",
+                "code_block.long_lines",
+            ),
+            (
+                "[style.heading.h2]\ncolor = \"blurple\"",
+                "style.heading.h2.color",
+            ),
+            ("[style.link]\nbackground = 3.5", "style.link.background"),
+            (
+                "[style.code]\nattributes = [\"shiny\"]",
+                "style.code.attributes",
+            ),
+            ("[style]\nparagraph = 1", "style.paragraph"),
+        ];
+        for (toml, key) in cases {
+            assert_eq!(error_key(toml), key, "error of {:?}", toml);
+        }
+    }
+
+    /// A fresh directory for a test, removed with its contents when dropped.
+    pub(super) struct TempDir(pub(super) PathBuf);
+
+    impl TempDir {
+        pub(super) fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("dear-md-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).expect("temporary directory");
+            Self(dir.canonicalize().expect("temporary directory"))
+        }
+
+        /// Creates `path` in the directory, as a directory when it ends with `/`.
+        pub(super) fn create(&self, path: &str) -> PathBuf {
+            let path = self.0.join(path);
+            match path.to_str().and_then(|path| path.strip_suffix('/')) {
+                Some(dir) => fs::create_dir_all(dir).expect("directory"),
+                None => {
+                    fs::create_dir_all(path.parent().expect("parent")).expect("directory");
+                    fs::write(&path, "").expect("file");
+                }
+            }
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn project_path_stops_at_the_repository_root() {
+        let dir = TempDir::new("project-path");
+        let above = dir.create(PROJECT_CONFIG);
+        dir.create("repo/.git/");
+        let inner = dir.create("repo/a/b/");
+        assert_eq!(Config::project_path(&inner), None);
+
+        let root = dir.create(&format!("repo/{}", PROJECT_CONFIG));
+        assert_eq!(Config::project_path(&inner), Some(root.clone()));
+        assert_eq!(Config::project_path(&dir.0.join("repo")), Some(root));
+
+        let nearest = dir.create(&format!("repo/a/{}", PROJECT_CONFIG));
+        assert_eq!(Config::project_path(&inner), Some(nearest));
+
+        // Outside of a repository, only the directory itself counts.
+        let outside = dir.create("outside/");
+        assert_eq!(Config::project_path(&outside), None);
+        assert_eq!(Config::project_path(&dir.0), Some(above));
+    }
+}
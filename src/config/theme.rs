@@ -0,0 +1,307 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml::{value::Table, Value};
+
+use super::{config_dir, default_table, merge, Section, Styles};
+use crate::error::{Error, Result};
+use crate::style::StyleSetBuilder;
+
+/// The themes that are always available, which theme files can extend.
+const BUILT_IN_THEMES: [(&str, &str); 4] = [
+    ("dark", include_str!("themes/dark.toml")),
+    ("light", include_str!("themes/light.toml")),
+    ("dracula", include_str!("themes/dracula.toml")),
+    ("solarized", include_str!("themes/solarized.toml")),
+];
+
+/// A named set of styles, read from `<name>.toml` in the themes directory or else one of the
+/// built-in themes. A theme file can `extend` another theme and override only some of its
+/// styles, and pick a `code_theme` for code blocks. A file extending its own name extends
+/// the built-in theme of that name.
+#[derive(Clone)]
+pub struct Theme {
+    name: String,
+    table: Table,
+    styles: Styles,
+}
+
+impl Theme {
+    /// Where theme files are looked for: `themes` in the config directory.
+    pub fn dir() -> Option<PathBuf> {
+        Some(config_dir()?.join("themes"))
+    }
+
+    /// The names of the built-in themes and of the theme files in the themes directory.
+    pub fn names() -> Vec<String> {
+        let mut names: Vec<String> = BUILT_IN_THEMES
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+        if let Some(entries) = Theme::dir().and_then(|dir| fs::read_dir(dir).ok()) {
+            for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "toml")
+                {
+                    if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                        names.push(name.to_owned());
+                    }
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Loads the theme called `name` from the themes directory or the built-in themes, with
+    /// the themes it extends, failing on unknown keys and invalid styles.
+    pub fn load(name: &str) -> Result<Self> {
+        Theme::load_from(name, Theme::dir().as_deref())
+    }
+
+    /// Loads the theme called `name` from `dir`, or from the built-in themes only when there
+    /// is no directory.
+    pub(crate) fn load_from(name: &str, dir: Option<&Path>) -> Result<Self> {
+        let table = load_table(name, dir, &mut vec![])?;
+
+        let mut config = default_table();
+        merge(&mut config, table.clone(), "", "", &mut Default::default());
+        let styles = Section::root(&config)
+            .section("style")
+            .and_then(|section| Styles::from_section(&section))
+            .map_err(|err| Error::Theme {
+                name: name.to_owned(),
+                err: Box::new(err),
+            })?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            table,
+            styles,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The settings of the theme in the layout of a config file.
+    pub(crate) fn table(&self) -> &Table {
+        &self.table
+    }
+}
+
+impl StyleSetBuilder {
+    /// Sets the styles of the theme called `name`, see [`Theme::load`]. This replaces the
+    /// styles of everything but code blocks set before, and the code block style is left as
+    /// it is.
+    pub fn theme(self, name: &str) -> Result<Self> {
+        Ok(Theme::load(name)?.styles.apply(self))
+    }
+}
+
+/// Reads the theme called `name` merged over the themes it extends, with its code theme as
+/// the `theme` of a config file. `extending` holds the themes that led to this one.
+fn load_table(name: &str, dir: Option<&Path>, extending: &mut Vec<String>) -> Result<Table> {
+    let theme_error = |err: Error| Error::Theme {
+        name: name.to_owned(),
+        err: Box::new(err),
+    };
+    if extending.iter().any(|extended| extended == name) {
+        return Err(Error::Config {
+            key: "extend".to_owned(),
+            message: format!("`{}` ends up extending itself", name),
+        });
+    }
+
+    let path = dir
+        .map(|dir| dir.join(format!("{}.toml", name)))
+        .filter(|path| path.is_file());
+    let from_file = path.is_some();
+    let theme = match path {
+        Some(path) => fs::read_to_string(path).map_err(|err| theme_error(err.into()))?,
+        None => match BUILT_IN_THEMES
+            .iter()
+            .find(|(built_in, _)| *built_in == name)
+        {
+            Some((_, theme)) => theme.to_string(),
+            None => return Err(Error::UnknownStyleTheme(name.to_owned())),
+        },
+    };
+    let mut theme: Table = toml::from_str(&theme).map_err(|err| theme_error(err.into()))?;
+
+    let root = Section::root(&theme);
+    root.check_keys(&["extend", "code_theme", "style"])
+        .map_err(theme_error)?;
+    let extend = root.string("extend").map_err(theme_error)?;
+    root.section("style").map_err(theme_error)?;
+    let code_theme = root.string("code_theme").map_err(theme_error)?;
+
+    let mut table = match extend {
+        Some(extend) if extend == name && from_file => {
+            load_table(&extend, None, extending).map_err(theme_error)?
+        }
+        Some(extend) => {
+            extending.push(name.to_owned());
+            load_table(&extend, dir, extending).map_err(theme_error)?
+        }
+        None => Table::new(),
+    };
+    theme.remove("extend");
+    theme.remove("code_theme");
+    if let Some(code_theme) = code_theme {
+        theme.insert("theme".to_owned(), Value::String(code_theme));
+    }
+    merge(&mut table, theme, "", "", &mut Default::default());
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::TempDir;
+
+    /// A themes directory with the given theme files.
+    fn themes(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new(name);
+        for (name, theme) in files {
+            fs::write(dir.0.join(format!("{}.toml", name)), theme).expect("theme file");
+        }
+        dir
+    }
+
+    /// The value at the dotted `key` of `table`.
+    fn value<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+        let mut keys = key.split('.');
+        let mut value = table.get(keys.next()?)?;
+        for key in keys {
+            value = value.get(key)?;
+        }
+        Some(value)
+    }
+
+    /// The error at the end of a chain of theme errors.
+    fn innermost(err: &Error) -> &Error {
+        match err {
+            Error::Theme { err, .. } => innermost(err),
+            err => err,
+        }
+    }
+
+    #[test]
+    fn built_in_themes_load_without_a_directory() {
+        for (name, _) in BUILT_IN_THEMES {
+            let theme = Theme::load_from(name, None).expect("valid built-in theme");
+            assert_eq!(theme.name(), name);
+            assert!(
+                value(theme.table(), "theme").is_some(),
+                "code theme of {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn extend_chains_merge_over_each_other() {
+        let dir = themes(
+            "extend-chains",
+            &[
+                ("a", "extend = \"b\"\n[style.heading]\ncolor = \"red\""),
+                (
+                    "b",
+                    "extend = \"dracula\"\ncode_theme = \"InspiredGitHub\"\n\
+                     [style.heading]\ncolor = \"green\"\n[style.paragraph]\ncolor = \"blue\"",
+                ),
+            ],
+        );
+        let theme = Theme::load_from("a", Some(&dir.0)).expect("valid theme");
+        let string = |key| value(theme.table(), key).and_then(Value::as_str);
+        assert_eq!(string("style.heading.color"), Some("red"));
+        assert_eq!(string("style.paragraph.color"), Some("blue"));
+        assert_eq!(string("style.heading.h1.color"), Some("#ff79c6"));
+        assert_eq!(string("theme"), Some("InspiredGitHub"));
+        assert_eq!(string("extend"), None);
+        assert_eq!(string("code_theme"), None);
+    }
+
+    #[test]
+    fn extending_its_own_name_extends_the_built_in_theme() {
+        let dir = themes(
+            "extend-self",
+            &[(
+                "dracula",
+                "extend = \"dracula\"\n[style.heading]\ncolor = \"red\"",
+            )],
+        );
+        let theme = Theme::load_from("dracula", Some(&dir.0)).expect("valid theme");
+        let string = |key| value(theme.table(), key).and_then(Value::as_str);
+        assert_eq!(string("style.heading.color"), Some("red"));
+        assert_eq!(string("style.heading.h1.color"), Some("#ff79c6"));
+    }
+
+    #[test]
+    fn extend_cycles_fail() {
+        let dir = themes(
+            "extend-cycles",
+            &[
+                ("a", "extend = \"b\""),
+                ("b", "extend = \"c\""),
+                ("c", "extend = \"a\""),
+            ],
+        );
+        match Theme::load_from("a", Some(&dir.0))
+            .err()
+            .as_ref()
+            .map(innermost)
+        {
+            Some(Error::Config { key, message }) => {
+                assert_eq!(key, "extend");
+                assert!(message.contains("`a`"), "{}", message);
+            }
+            err => panic!("unexpected result: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn unknown_themes_fail() {
+        let dir = themes("unknown", &[("a", "extend = \"nope\"")]);
+        for name in ["nope", "a"] {
+            match Theme::load_from(name, Some(&dir.0))
+                .err()
+                .as_ref()
+                .map(innermost)
+            {
+                Some(Error::UnknownStyleTheme(theme)) => assert_eq!(theme, "nope"),
+                err => panic!("unexpected result for {}: {:?}", name, err),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_theme_files_name_the_theme_and_key() {
+        let cases = [
+            ("colour = \"red\"", "colour"),
+            ("extend = 1", "extend"),
+            ("[style.heading]\ncolour = \"red\"", "style.heading.colour"),
+            ("[style.link]\ncolor = \"blurple\"", "style.link.color"),
+        ];
+        for (theme, expected) in cases {
+            let dir = themes("invalid", &[("a", theme)]);
+            match Theme::load_from("a", Some(&dir.0)).err() {
+                Some(Error::Theme { name, err }) => match *err {
+                    Error::Config { key, .. } => {
+                        assert_eq!(name, "a");
+                        assert_eq!(key, expected, "error of {:?}", theme);
+                    }
+                    err => panic!("unexpected error for {:?}: {}", theme, err),
+                },
+                Some(err) => panic!("unexpected error for {:?}: {}", theme, err),
+                None => panic!("no error for {:?}", theme),
+            }
+        }
+    }
+}
@@ -1,74 +1,291 @@
+pub(crate) mod table;
+pub(crate) use table::Table;
+
 use crossterm::style::Attribute;
 use pulldown_cmark::Tag;
 
+use crate::layout::Line;
+use crate::style::{FenceInfo, LinkMode};
+
+/// A block that has started but not ended yet.
+#[derive(Debug)]
+pub(crate) struct OpenBlock<'a> {
+    tag: Tag<'a>,
+    marker_pending: bool,
+    checked: Option<bool>,
+}
+
+impl<'a> OpenBlock<'a> {
+    pub(crate) fn tag(&self) -> &Tag<'a> {
+        &self.tag
+    }
+
+    /// Whether the list marker of an item still has to be printed.
+    pub(crate) fn marker_pending(&self) -> bool {
+        self.marker_pending
+    }
+
+    /// The state of the checkbox of a task list item.
+    pub(crate) fn checked(&self) -> Option<bool> {
+        self.checked
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Context<'a> {
-    current_block: Tag<'a>,
-    code_block_syntax: String,
-    indentation: usize,
+    blocks: Vec<OpenBlock<'a>>,
+    code_block_info: FenceInfo,
+    code_block_text: String,
     modifiers: Vec<Attribute>,
-    start_of_line: bool,
+    separator_pending: bool,
+    table: Option<Table>,
+    footnote_labels: Vec<String>,
+    footnotes: Vec<(usize, Vec<Line>)>,
+    link_mode: LinkMode,
+    link: Option<String>,
+    references: Vec<String>,
+    heading: Option<(usize, String)>,
 }
 
 impl<'a> Context<'a> {
     fn new() -> Self {
         Self {
-            current_block: Tag::Paragraph,
-            code_block_syntax: "Plain Text".to_owned(),
-            indentation: 0,
+            blocks: vec![],
+            code_block_info: FenceInfo::default(),
+            code_block_text: String::new(),
             modifiers: vec![],
-            start_of_line: true,
+            separator_pending: false,
+            table: None,
+            footnote_labels: vec![],
+            footnotes: vec![],
+            link_mode: LinkMode::default(),
+            link: None,
+            references: vec![],
+            heading: None,
+        }
+    }
+
+    /// What a top-level block leaves for the ones after it, such as footnote numbers and
+    /// references, so that the rest of a document can be laid out on its own.
+    pub(crate) fn between_blocks<'b>(&self) -> Context<'b> {
+        Context {
+            separator_pending: self.separator_pending,
+            footnote_labels: self.footnote_labels.clone(),
+            footnotes: self.footnotes.clone(),
+            link_mode: self.link_mode,
+            references: self.references.clone(),
+            ..Context::new()
+        }
+    }
+
+    /// Every open block, from the outermost container to the current block.
+    pub(crate) fn blocks(&self) -> &[OpenBlock<'a>] {
+        &self.blocks
+    }
+
+    pub(crate) fn current_block(&self) -> Option<&Tag<'a>> {
+        self.blocks.last().map(OpenBlock::tag)
+    }
+
+    /// The innermost open block quote, list or list item.
+    pub(crate) fn container(&self) -> Option<&Tag<'a>> {
+        self.blocks
+            .iter()
+            .rev()
+            .map(OpenBlock::tag)
+            .find(|tag| matches!(tag, Tag::BlockQuote | Tag::List(_) | Tag::Item))
+    }
+
+    /// The innermost open list.
+    pub(crate) fn current_list(&self) -> Option<&Tag<'a>> {
+        self.blocks
+            .iter()
+            .rev()
+            .map(OpenBlock::tag)
+            .find(|tag| matches!(tag, Tag::List(_)))
+    }
+
+    pub(crate) fn push_block(&mut self, block: Tag<'a>) {
+        let marker_pending = matches!(block, Tag::Item | Tag::FootnoteDefinition(_));
+        self.blocks.push(OpenBlock {
+            tag: block,
+            marker_pending,
+            checked: None,
+        });
+    }
+
+    pub(crate) fn pop_block(&mut self) -> Option<Tag<'a>> {
+        self.blocks.pop().map(|block| block.tag)
+    }
+
+    /// Moves the innermost ordered list on to its next item number.
+    pub(crate) fn next_item(&mut self) {
+        let list = self
+            .blocks
+            .iter_mut()
+            .rev()
+            .find(|block| matches!(block.tag, Tag::List(_)));
+        if let Some(OpenBlock {
+            tag: Tag::List(Some(order)),
+            ..
+        }) = list
+        {
+            *order += 1;
+        }
+    }
+
+    /// Turns the innermost list item into a task list item.
+    pub(crate) fn set_checked(&mut self, checked: bool) {
+        let item = self
+            .blocks
+            .iter_mut()
+            .rev()
+            .find(|block| matches!(block.tag, Tag::Item));
+        if let Some(item) = item {
+            item.checked = Some(checked);
+        }
+    }
+
+    pub(crate) fn set_markers_printed(&mut self) {
+        for block in self.blocks.iter_mut() {
+            block.marker_pending = false;
         }
     }
 
-    pub(crate) fn current_block(&self) -> &Tag<'a> {
-        &self.current_block
+    /// The language and attributes of the current code block.
+    pub(crate) fn code_block_info(&self) -> &FenceInfo {
+        &self.code_block_info
+    }
+
+    pub(crate) fn set_code_block_info(&mut self, code_block_info: FenceInfo) {
+        self.code_block_info = code_block_info;
+    }
+
+    /// Collects the text of the current code block, which is highlighted as a whole once
+    /// the block ends.
+    pub(crate) fn push_code_block_text(&mut self, text: &str) {
+        self.code_block_text.push_str(text);
+    }
+
+    pub(crate) fn take_code_block_text(&mut self) -> String {
+        std::mem::take(&mut self.code_block_text)
+    }
+
+    /// Whether a blank line should go before the next block.
+    pub(crate) fn separator_pending(&self) -> &bool {
+        &self.separator_pending
     }
 
-    pub(crate) fn set_current_block(&mut self, block: Tag<'a>) -> () {
-        self.current_block = block;
+    pub(crate) fn set_separator_pending(&mut self, separator_pending: bool) {
+        self.separator_pending = separator_pending;
     }
 
-    pub(crate) fn code_block_syntax(&self) -> &str {
-        &self.code_block_syntax
+    /// The table being collected, if a table is open.
+    pub(crate) fn table(&mut self) -> Option<&mut Table> {
+        self.table.as_mut()
+    }
+
+    pub(crate) fn set_table(&mut self, table: Option<Table>) {
+        self.table = table;
+    }
+
+    pub(crate) fn take_table(&mut self) -> Option<Table> {
+        self.table.take()
+    }
+
+    /// Whether inline content goes into a table cell instead of a line.
+    pub(crate) fn in_table_cell(&self) -> bool {
+        matches!(self.current_block(), Some(Tag::TableCell))
+    }
+
+    /// The number of the footnote called `label`, numbered in order of first appearance.
+    pub(crate) fn footnote_number(&mut self, label: &str) -> usize {
+        match self.footnote_labels.iter().position(|known| known == label) {
+            Some(index) => index + 1,
+            None => {
+                self.footnote_labels.push(label.to_owned());
+                self.footnote_labels.len()
+            }
+        }
     }
 
-    pub(crate) fn set_code_block_syntax(&mut self, code_block_syntax: &str) -> () {
-        self.code_block_syntax = code_block_syntax.to_owned();
+    pub(crate) fn find_footnote_number(&self, label: &str) -> Option<usize> {
+        self.footnote_labels
+            .iter()
+            .position(|known| known == label)
+            .map(|index| index + 1)
     }
 
-    pub(crate) fn indentation(&self) -> &usize {
-        &self.indentation
+    pub(crate) fn add_footnote(&mut self, number: usize, lines: Vec<Line>) {
+        self.footnotes.push((number, lines));
     }
 
-    pub(crate) fn set_indentation(&mut self, level: usize) -> () {
-        self.indentation = level;
+    /// Every footnote definition seen so far, in footnote number order.
+    pub(crate) fn take_footnotes(&mut self) -> Vec<(usize, Vec<Line>)> {
+        let mut footnotes = std::mem::take(&mut self.footnotes);
+        footnotes.sort_by_key(|(number, _)| *number);
+        footnotes
     }
 
-    pub(crate) fn start_of_line(&self) -> &bool {
-        &self.start_of_line
+    pub(crate) fn link_mode(&self) -> LinkMode {
+        self.link_mode
     }
 
-    pub(crate) fn set_start_of_line(&mut self, start_of_line: bool) {
-        self.start_of_line = start_of_line;
+    pub(crate) fn set_link_mode(&mut self, link_mode: LinkMode) {
+        self.link_mode = link_mode;
+    }
+
+    /// The URL of the link the text is part of.
+    pub(crate) fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+
+    pub(crate) fn set_link(&mut self, link: Option<&str>) {
+        self.link = link.map(str::to_owned);
+    }
+
+    /// The number of the reference to `url`, numbered in order of first appearance.
+    pub(crate) fn reference_number(&mut self, url: &str) -> usize {
+        match self.references.iter().position(|known| known == url) {
+            Some(index) => index + 1,
+            None => {
+                self.references.push(url.to_owned());
+                self.references.len()
+            }
+        }
+    }
+
+    pub(crate) fn take_references(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.references)
+    }
+
+    /// Starts collecting the text of a heading whose first line is `line`.
+    pub(crate) fn start_heading(&mut self, line: usize) {
+        self.heading = Some((line, String::new()));
+    }
+
+    pub(crate) fn push_heading_text(&mut self, text: &str) {
+        if let Some((_, heading)) = &mut self.heading {
+            heading.push_str(text);
+        }
+    }
+
+    pub(crate) fn take_heading(&mut self) -> Option<(usize, String)> {
+        self.heading.take()
     }
 
     pub(crate) fn modifiers(&self) -> &Vec<Attribute> {
         &self.modifiers
     }
 
-    pub(crate) fn add_modifier(&mut self, modifier: Attribute) -> () {
+    pub(crate) fn add_modifier(&mut self, modifier: Attribute) {
         self.modifiers.push(modifier)
     }
 
-    pub(crate) fn remove_modifier(&mut self, modifier: Attribute) -> () {
-        // TODO: Modify the way we are dealing with a possible error by checking if the item is in the vector beforehand
-        self.modifiers.remove(
-            self.modifiers
-                .iter()
-                .position(|attr| *attr == modifier)
-                .unwrap(),
-        );
+    pub(crate) fn remove_modifier(&mut self, modifier: Attribute) {
+        if let Some(position) = self.modifiers.iter().position(|attr| *attr == modifier) {
+            self.modifiers.remove(position);
+        }
     }
 }
 
@@ -0,0 +1,54 @@
+use pulldown_cmark::Alignment;
+
+use crate::style::Content;
+
+/// The cells of a table collected while its events come in, rendered once the table ends.
+#[derive(Debug)]
+pub(crate) struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Content>>>,
+    head_rows: usize,
+}
+
+impl Table {
+    pub(crate) fn new(alignments: &[Alignment]) -> Self {
+        Self {
+            alignments: alignments.to_vec(),
+            rows: vec![],
+            head_rows: 0,
+        }
+    }
+
+    pub(crate) fn alignments(&self) -> &[Alignment] {
+        &self.alignments
+    }
+
+    pub(crate) fn rows(&self) -> &[Vec<Vec<Content>>] {
+        &self.rows
+    }
+
+    /// Number of rows at the top of the table that make up its header.
+    pub(crate) fn head_rows(&self) -> &usize {
+        &self.head_rows
+    }
+
+    pub(crate) fn start_row(&mut self) {
+        self.rows.push(vec![]);
+    }
+
+    pub(crate) fn end_head(&mut self) {
+        self.head_rows = self.rows.len();
+    }
+
+    pub(crate) fn start_cell(&mut self) {
+        if let Some(row) = self.rows.last_mut() {
+            row.push(vec![]);
+        }
+    }
+
+    pub(crate) fn push_content(&mut self, contents: Vec<Content>) {
+        if let Some(cell) = self.rows.last_mut().and_then(|row| row.last_mut()) {
+            cell.extend(contents);
+        }
+    }
+}
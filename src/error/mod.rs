@@ -0,0 +1,98 @@
+use std::{fmt, io};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Reading the markdown input failed.
+    Io(io::Error),
+    /// The code block theme is neither a built-in theme nor a theme file.
+    UnknownTheme(String),
+    /// The code block theme file could not be loaded.
+    ThemeLoading(syntect::LoadingError),
+    /// Highlighting a code block line failed.
+    Highlighting(syntect::Error),
+    /// Writing to the terminal or output sink failed.
+    Terminal(io::Error),
+    /// No heading matches the section path, listing the sections that could have matched.
+    UnknownSection {
+        section: String,
+        choices: Vec<String>,
+    },
+    /// More than one heading matches the section path.
+    AmbiguousSection {
+        section: String,
+        matches: Vec<String>,
+    },
+    /// The config file is not valid TOML.
+    ConfigParsing(toml::de::Error),
+    /// A config key is unknown or has an invalid value.
+    Config { key: String, message: String },
+    /// The style theme is neither a built-in theme nor a file in the themes directory.
+    UnknownStyleTheme(String),
+    /// Loading a style theme or a theme it extends failed.
+    Theme { name: String, err: Box<Error> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::UnknownTheme(theme) => write!(f, "unknown theme `{}`", theme),
+            Error::ThemeLoading(err) => write!(f, "could not load theme: {}", err),
+            Error::Highlighting(err) => write!(f, "could not highlight code block: {}", err),
+            Error::Terminal(err) => write!(f, "could not write output: {}", err),
+            Error::UnknownSection { section, .. } => {
+                write!(f, "no section matches `{}`", section)
+            }
+            Error::AmbiguousSection { section, .. } => {
+                write!(f, "more than one section matches `{}`", section)
+            }
+            Error::ConfigParsing(err) => write!(f, "could not parse config: {}", err),
+            Error::Config { key, message } => write!(f, "config key `{}`: {}", key, message),
+            Error::UnknownStyleTheme(theme) => write!(f, "unknown style theme `{}`", theme),
+            Error::Theme { name, err } => write!(f, "theme `{}`: {}", name, err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) | Error::Terminal(err) => Some(err),
+            Error::ThemeLoading(err) => Some(err),
+            Error::Highlighting(err) => Some(err),
+            Error::ConfigParsing(err) => Some(err),
+            Error::Theme { err, .. } => Some(err.as_ref()),
+            Error::UnknownTheme(_)
+            | Error::UnknownSection { .. }
+            | Error::AmbiguousSection { .. }
+            | Error::Config { .. }
+            | Error::UnknownStyleTheme(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<syntect::LoadingError> for Error {
+    fn from(err: syntect::LoadingError) -> Self {
+        Error::ThemeLoading(err)
+    }
+}
+
+impl From<syntect::Error> for Error {
+    fn from(err: syntect::Error) -> Self {
+        Error::Highlighting(err)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Error::ConfigParsing(err)
+    }
+}
@@ -1,41 +1,86 @@
-use pulldown_cmark::Event;
+use pulldown_cmark::{Event, Tag};
 
 use crate::context::Context;
+use crate::error::Result;
+use crate::layout::{BlockKind, LayoutBuilder};
 use crate::style::{Content, StyleSet};
 
-use super::{StdoutHandler, TagHandler};
+use super::{push_inline, TagHandler};
 
 pub(crate) trait EventHandler<'a> {
-    fn handle(&self, context: &mut Context<'a>, stdout: &mut StdoutHandler, style_set: &StyleSet);
+    fn handle(
+        &self,
+        context: &mut Context<'a>,
+        layout: &mut LayoutBuilder,
+        style_set: &StyleSet,
+    ) -> Result<()>;
 }
 
 impl<'a> EventHandler<'a> for Event<'a> {
-    fn handle(&self, context: &mut Context<'a>, stdout: &mut StdoutHandler, style_set: &StyleSet) {
-        // println!("{:?}", self);
+    fn handle(
+        &self,
+        context: &mut Context<'a>,
+        layout: &mut LayoutBuilder,
+        style_set: &StyleSet,
+    ) -> Result<()> {
         match self {
-            Event::Start(tag) => tag.start(context),
-            Event::End(tag) => tag.end(context, stdout),
-            Event::Text(text) => {
-                context
-                    .current_block()
-                    .clone()
-                    .handle_text(context, stdout, style_set, &text);
+            Event::Start(tag) => {
+                tag.start(context, layout, style_set);
+                Ok(())
             }
+            Event::End(tag) => tag.end(context, layout, style_set),
+            Event::Text(text) => context
+                .current_block()
+                .cloned()
+                .unwrap_or(Tag::Paragraph)
+                .handle_text(context, layout, style_set, text),
             Event::Code(text) => {
-                stdout.queue_styled_content_v2(style_set.code().get_styled_content(&text, context));
+                context.push_heading_text(text);
+                let contents = style_set.code().get_styled_content(text, context);
+                push_inline(context, layout, style_set, contents);
+                Ok(())
             }
             Event::SoftBreak => {
-                stdout.queue_styled_content_v2(vec![Content::String(" ".to_string())]);
+                push_inline(
+                    context,
+                    layout,
+                    style_set,
+                    vec![Content::String(" ".to_string())],
+                );
+                Ok(())
+            }
+            Event::FootnoteReference(label) => {
+                let number = context.footnote_number(label);
+                let contents = style_set.footnote().get_styled_content(number, context);
+                push_inline(context, layout, style_set, contents);
+                Ok(())
+            }
+            Event::TaskListMarker(checked) => {
+                context.set_checked(*checked);
+                Ok(())
             }
             Event::HardBreak => {
-                context.set_start_of_line(true);
-                stdout.queue_styled_content_v2(vec![Content::String("\n".to_string())]);
+                layout.new_line();
+                Ok(())
             }
             Event::Rule => {
-                context.set_start_of_line(true);
-                stdout.queue_styled_content_v2(style_set.rule().get_styled_content());
+                if context.blocks().is_empty() {
+                    layout.start_block(BlockKind::Rule);
+                }
+                layout.end_line();
+                if *context.separator_pending() {
+                    layout.push_content(style_set.separator(context));
+                    layout.new_line();
+                }
+                layout.push_content(style_set.prefix(context, true));
+                context.set_markers_printed();
+                let width = layout.width().saturating_sub(layout.column());
+                layout.push_content(style_set.rule().get_styled_content(width));
+                layout.end_line();
+                context.set_separator_pending(true);
+                Ok(())
             }
-            _ => (),
+            _ => Ok(()),
         }
     }
 }
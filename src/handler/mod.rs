@@ -4,5 +4,69 @@ pub(crate) use tag::TagHandler;
 pub(crate) mod event;
 pub(crate) use event::EventHandler;
 
-pub(crate) mod stdout;
-pub(crate) use stdout::StdoutHandler;
+pub(crate) mod output;
+pub(crate) use output::OutputHandler;
+
+use crate::context::Context;
+use crate::layout::{BlockKind, LayoutBuilder};
+use crate::style::{Content, StyleSet};
+
+use unicode_width::UnicodeWidthStr;
+
+/// Pushes inline content, starting with the prefix of the open blocks when it begins a line.
+pub(crate) fn push_inline(
+    context: &mut Context,
+    layout: &mut LayoutBuilder,
+    style_set: &StyleSet,
+    contents: Vec<Content>,
+) {
+    if context.in_table_cell() {
+        if let Some(table) = context.table() {
+            table.push_content(contents);
+        }
+        return;
+    }
+    if layout.at_line_start() {
+        layout.push_content(style_set.prefix(context, true));
+        context.set_markers_printed();
+    }
+    context.set_separator_pending(false);
+    layout.push_wrapped_content(contents, style_set.prefix(context, true));
+}
+
+/// Appends what is collected over the whole document, such as footnote definitions.
+pub(crate) fn finish(context: &mut Context, layout: &mut LayoutBuilder, style_set: &StyleSet) {
+    let footnotes = context.take_footnotes();
+    if !footnotes.is_empty() {
+        layout.start_block(BlockKind::Footnotes);
+        if *context.separator_pending() {
+            layout.new_line();
+        }
+        layout.push_content(vec![style_set.footnote().rule()]);
+        layout.end_line();
+        for (_, lines) in footnotes {
+            layout.push_lines(lines);
+        }
+        context.set_separator_pending(true);
+    }
+
+    let references = context.take_references();
+    if !references.is_empty() {
+        layout.start_block(BlockKind::References);
+        if *context.separator_pending() {
+            layout.new_line();
+        }
+        layout.push_content(style_set.link().references_title());
+        layout.end_line();
+        for (index, url) in references.iter().enumerate() {
+            let marker = style_set.link().reference_marker(index + 1);
+            let indent: usize = marker.iter().map(|c| c.text().width()).sum();
+            layout.push_content(marker);
+            layout.push_wrapped_content(
+                style_set.link().reference_url(url),
+                vec![Content::String(" ".repeat(indent))],
+            );
+            layout.end_line();
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crossterm::{
+    queue,
+    style::{Print, PrintStyledContent, ResetColor},
+};
+use std::io::Write;
+
+use crate::error::{Error, Result};
+use crate::layout::{Document, Line};
+
+pub(crate) struct OutputHandler<W: Write> {
+    writer: W,
+    color: bool,
+}
+
+impl<W: Write> OutputHandler<W> {
+    pub(crate) fn new(writer: W, color: bool) -> Self {
+        Self { writer, color }
+    }
+
+    pub(crate) fn writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub(crate) fn queue_document(&mut self, document: &Document) -> Result<()> {
+        for line in document.lines() {
+            self.queue_line(line)?;
+            self.queue_new_line()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn queue_new_line(&mut self) -> Result<()> {
+        queue!(self.writer, Print("\n")).map_err(Error::Terminal)
+    }
+
+    pub(crate) fn queue_line(&mut self, line: &Line) -> Result<()> {
+        let mut link = None;
+        for span in line.spans() {
+            if self.color && span.link() != link {
+                link = span.link();
+                self.queue_hyperlink(link.unwrap_or_default())?;
+            }
+            if self.color {
+                queue!(
+                    self.writer,
+                    PrintStyledContent(span.style().apply(span.text())),
+                    ResetColor
+                )
+            } else {
+                queue!(self.writer, Print(span.text()))
+            }
+            .map_err(Error::Terminal)?;
+        }
+        if link.is_some() {
+            self.queue_hyperlink("")?;
+        }
+        Ok(())
+    }
+
+    /// Opens an OSC 8 hyperlink to `url`, or closes the open one when `url` is empty.
+    fn queue_hyperlink(&mut self, url: &str) -> Result<()> {
+        queue!(self.writer, Print(format!("\x1b]8;;{}\x1b\\", url))).map_err(Error::Terminal)
+    }
+
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(Error::Terminal)
+    }
+}
@@ -1,111 +1,167 @@
-use super::StdoutHandler;
+use super::push_inline;
 
-use crate::context::Context;
-use crate::style::{Content, StyleSet};
+use crate::context::{Context, Table};
+use crate::error::Result;
+use crate::layout::{heading_number, BlockKind, Heading, LayoutBuilder};
+use crate::style::{Content, FenceInfo, LinkMode, StyleSet};
 
 use crossterm::style::Attribute;
-use pulldown_cmark::{CodeBlockKind, HeadingLevel, Tag};
+use pulldown_cmark::{CodeBlockKind, LinkType, Tag};
+use unicode_width::UnicodeWidthStr;
 
-fn capitalize(s: &str) -> String {
-    let mut c = s.chars();
-    match c.next() {
-        None => String::new(),
-        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
-    }
+fn is_block(tag: &Tag) -> bool {
+    !matches!(
+        tag,
+        Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) | Tag::Image(..)
+    )
+}
+
+/// Rows and cells are laid out by the table itself once it ends.
+fn is_table_part(tag: &Tag) -> bool {
+    matches!(tag, Tag::TableHead | Tag::TableRow | Tag::TableCell)
 }
 
 pub(crate) trait TagHandler<'a> {
-    fn start(&self, context: &mut Context<'a>);
-    fn end(&self, context: &mut Context<'a>, stdout: &mut StdoutHandler);
+    fn start(&self, context: &mut Context<'a>, layout: &mut LayoutBuilder, style_set: &StyleSet);
+    fn end(
+        &self,
+        context: &mut Context<'a>,
+        layout: &mut LayoutBuilder,
+        style_set: &StyleSet,
+    ) -> Result<()>;
     fn handle_text(
         &self,
         context: &mut Context<'a>,
-        stdout: &mut StdoutHandler,
+        layout: &mut LayoutBuilder,
         style_set: &StyleSet,
         text: &str,
-    );
+    ) -> Result<()>;
 }
 
 impl<'a> TagHandler<'a> for Tag<'a> {
-    //  TODO: Think about having a prefix modifier with some lifecycle instead of actually queuing here
-    fn start(&self, context: &mut Context<'a>) {
+    fn start(&self, context: &mut Context<'a>, layout: &mut LayoutBuilder, style_set: &StyleSet) {
+        if let Tag::FootnoteDefinition(label) = self {
+            // Definitions are laid out aside and appended at the end of the document.
+            context.footnote_number(label);
+            layout.start_capture();
+            context.push_block(self.clone());
+            context.set_separator_pending(false);
+            return;
+        }
+        if is_block(self) && !is_table_part(self) {
+            if context.blocks().is_empty() {
+                layout.start_block(BlockKind::from_tag(self));
+            }
+            layout.end_line();
+            if *context.separator_pending() && !matches!(self, Tag::Item) {
+                layout.push_content(style_set.separator(context));
+                layout.new_line();
+                context.set_separator_pending(false);
+            }
+        }
+        if is_block(self) {
+            context.push_block(self.clone());
+        }
         match self {
-            Tag::Paragraph => {
-                if std::mem::discriminant(context.current_block())
-                    != std::mem::discriminant(&Tag::BlockQuote)
-                {
-                    context.set_current_block(self.clone());
+            Tag::Table(alignments) => {
+                context.set_table(Some(Table::new(alignments)));
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = context.table() {
+                    table.start_row();
                 }
-                context.set_start_of_line(true);
             }
-            Tag::Heading(..) => {
-                context.set_current_block(self.clone());
+            Tag::TableCell => {
+                if let Some(table) = context.table() {
+                    table.start_cell();
+                }
             }
             Tag::CodeBlock(kind) => {
-                context.set_current_block(self.clone());
-                context.set_indentation(context.indentation() + 1);
+                context.take_code_block_text();
                 match kind {
                     CodeBlockKind::Indented => {
-                        context.set_code_block_syntax("Plain Text");
+                        context.set_code_block_info(FenceInfo::default());
                     }
-                    CodeBlockKind::Fenced(syntax) => {
-                        context.set_code_block_syntax(&capitalize(syntax));
+                    CodeBlockKind::Fenced(info) => {
+                        context.set_code_block_info(FenceInfo::parse(info));
                     }
                 }
             }
-            Tag::BlockQuote => {
-                context.set_current_block(self.clone());
-            }
-            Tag::List(_) => {
-                if std::mem::discriminant(context.current_block())
-                    == std::mem::discriminant(&Tag::List(None))
-                {
-                    context.set_indentation(context.indentation() + 1);
-                }
-                context.set_current_block(self.clone());
-            }
-            Tag::Item => {
-                context.set_start_of_line(true);
-            }
             Tag::Emphasis => {
                 context.add_modifier(Attribute::Underlined);
             }
             Tag::Strong => {
                 context.add_modifier(Attribute::Bold);
             }
+            Tag::Strikethrough => {
+                context.add_modifier(style_set.strikethrough().attribute());
+            }
+            Tag::Heading(..) => {
+                context.start_heading(layout.line_index());
+            }
+            Tag::Link(_, url, _) => {
+                context.set_link(Some(url));
+                // Tables are laid out from their cells' contents, which don't keep hyperlinks.
+                if context.link_mode() == LinkMode::Hyperlink && !context.in_table_cell() {
+                    layout.set_link(Some(url));
+                }
+            }
             _ => (),
         }
     }
 
-    fn end(&self, context: &mut Context<'a>, stdout: &mut StdoutHandler) {
-        match self {
-            Tag::Paragraph => {
-                stdout.queue_styled_content_v2(vec![Content::String("\n\n".to_string())]);
-            }
-            Tag::Heading(..) => {
-                stdout.queue_styled_content_v2(vec![Content::String("\n\n".to_string())]);
+    fn end(
+        &self,
+        context: &mut Context<'a>,
+        layout: &mut LayoutBuilder,
+        style_set: &StyleSet,
+    ) -> Result<()> {
+        if is_block(self) {
+            if !is_table_part(self) {
+                layout.end_line();
             }
-            Tag::CodeBlock(..) => {
-                context.set_indentation(context.indentation() - 1);
-                stdout.queue_styled_content_v2(vec![Content::String("\n".to_string())]);
+            context.pop_block();
+        }
+        match self {
+            Tag::Table(_) => {
+                if let Some(table) = context.take_table() {
+                    let prefix = style_set.prefix(context, true);
+                    let prefix_width: usize =
+                        prefix.iter().map(|content| content.text().width()).sum();
+                    let width = layout.width().saturating_sub(prefix_width);
+                    for line in style_set.table().get_table_content(&table, width) {
+                        layout.push_content(style_set.prefix(context, true));
+                        context.set_markers_printed();
+                        layout.push_content(line);
+                        layout.end_line();
+                    }
+                }
+                context.set_separator_pending(true);
             }
-            Tag::BlockQuote => {
-                stdout.queue_styled_content_v2(vec![Content::String("\n".to_string())]);
+            Tag::TableHead => {
+                if let Some(table) = context.table() {
+                    table.end_head();
+                }
             }
-            Tag::List(_) => {
-                if context.indentation() > &0 {
-                    context.set_indentation(context.indentation() - 1);
+            Tag::CodeBlock(_) => {
+                let text = context.take_code_block_text();
+                let prefix = style_set.prefix(context, true);
+                let prefix_width: usize = prefix.iter().map(|content| content.text().width()).sum();
+                let width = layout.width().saturating_sub(prefix_width);
+                for line in style_set
+                    .code_block()
+                    .get_styled_content(&text, context, width)?
+                {
+                    layout.push_content(style_set.prefix(context, true));
+                    context.set_markers_printed();
+                    layout.push_content(line);
+                    layout.end_line();
                 }
-                stdout.queue_styled_content_v2(vec![Content::String("\n".to_string())]);
+                context.set_separator_pending(true);
             }
+            tag if is_table_part(tag) => (),
             Tag::Item => {
-                let current_block = context.current_block().clone();
-                match current_block {
-                    Tag::List(Some(order)) => {
-                        context.set_current_block(Tag::List(Some(order + 1)));
-                    }
-                    _ => {}
-                }
+                context.next_item();
             }
             Tag::Emphasis => {
                 context.remove_modifier(Attribute::Underlined);
@@ -113,68 +169,100 @@ impl<'a> TagHandler<'a> for Tag<'a> {
             Tag::Strong => {
                 context.remove_modifier(Attribute::Bold);
             }
+            Tag::Strikethrough => {
+                context.remove_modifier(style_set.strikethrough().attribute());
+            }
+            Tag::Heading(level, ..) => {
+                if let Some((line, text)) = context.take_heading() {
+                    let level = heading_number(*level);
+                    layout.push_heading(Heading::new(level, text.trim(), line));
+                }
+                context.set_separator_pending(true);
+            }
+            Tag::Link(kind, url, _) => {
+                context.set_link(None);
+                layout.set_link(None);
+                let shows_url = matches!(kind, LinkType::Autolink | LinkType::Email);
+                match context.link_mode() {
+                    LinkMode::References => {
+                        let number = context.reference_number(url);
+                        push_inline(
+                            context,
+                            layout,
+                            style_set,
+                            style_set.link().reference(number),
+                        );
+                    }
+                    LinkMode::Hyperlink if !context.in_table_cell() => (),
+                    _ if !shows_url => {
+                        push_inline(context, layout, style_set, style_set.link().url(url));
+                    }
+                    _ => (),
+                }
+            }
+            Tag::Image(_, url, _) if context.link_mode() == LinkMode::References => {
+                let number = context.reference_number(url);
+                push_inline(
+                    context,
+                    layout,
+                    style_set,
+                    style_set.link().reference(number),
+                );
+            }
+            Tag::FootnoteDefinition(label) => {
+                let lines = layout.end_capture();
+                let number = context.footnote_number(label);
+                context.add_footnote(number, lines);
+                context.set_separator_pending(!layout.is_empty());
+            }
+            tag if is_block(tag) => {
+                context.set_separator_pending(true);
+            }
             _ => (),
         }
+        Ok(())
     }
 
     fn handle_text(
         &self,
         context: &mut Context<'a>,
-        stdout: &mut StdoutHandler,
+        layout: &mut LayoutBuilder,
         style_set: &StyleSet,
         text: &str,
-    ) {
-        match self {
+    ) -> Result<()> {
+        context.push_heading_text(text);
+        let contents = match self {
             Tag::CodeBlock(_) => {
-                stdout.queue_styled_content_v2(
-                    style_set.code_block().get_styled_content(text, context),
-                );
+                context.push_code_block_text(text);
+                return Ok(());
             }
-            Tag::List(order) => {
-                let content = match order {
-                    Some(o) => style_set
-                        .ordered_list()
-                        .get_styled_content(text, context, o),
-                    None => style_set.unordered_list().get_styled_content(text, context),
-                };
-                stdout.queue_styled_content_v2(content);
-            }
-            Tag::BlockQuote => {
-                let styled_content = style_set.block_quote().get_styled_content(text, context);
-                stdout.queue_styled_content_v2(styled_content)
-            }
-            _ => {
-                let contents = match context.current_block() {
-                    Tag::Paragraph => style_set.paragraph().get_styled_content(text, context),
-                    Tag::Heading(level, ..) => style_set
-                        .heading(HeadingLevelWrapper::new(level).into())
-                        .get_styled_content(text, context),
-                    _ => style_set.default().get_styled_content(text, context),
-                };
-                stdout.queue_styled_content_v2(contents);
+            _ if context.link().is_some() => style_set.link().get_styled_content(text, context),
+            Tag::Heading(level, ..) => style_set
+                .heading(heading_number(*level))
+                .get_styled_content(text, context),
+            Tag::Item => list_text(context, style_set, text),
+            Tag::TableCell => {
+                let header = context
+                    .blocks()
+                    .iter()
+                    .any(|block| matches!(block.tag(), Tag::TableHead));
+                style_set.table().get_styled_content(text, context, header)
             }
-        }
-        context.set_start_of_line(false);
+            Tag::Paragraph => match context.container() {
+                Some(Tag::BlockQuote) => style_set.block_quote().get_styled_content(text, context),
+                Some(Tag::Item) => list_text(context, style_set, text),
+                _ => style_set.paragraph().get_styled_content(text, context),
+            },
+            _ => style_set.default().get_styled_content(text, context),
+        };
+        push_inline(context, layout, style_set, contents);
+        Ok(())
     }
 }
 
-struct HeadingLevelWrapper(HeadingLevel);
-
-impl HeadingLevelWrapper {
-    fn new(level: &HeadingLevel) -> Self {
-        Self(level.clone())
-    }
-}
-
-impl From<HeadingLevelWrapper> for usize {
-    fn from(level_wrapper: HeadingLevelWrapper) -> Self {
-        match level_wrapper.0 {
-            HeadingLevel::H1 => 0,
-            HeadingLevel::H2 => 1,
-            HeadingLevel::H3 => 2,
-            HeadingLevel::H4 => 3,
-            HeadingLevel::H5 => 4,
-            HeadingLevel::H6 => 5,
-        }
+fn list_text(context: &Context, style_set: &StyleSet, text: &str) -> Vec<Content> {
+    match context.current_list() {
+        Some(Tag::List(Some(_))) => style_set.ordered_list().get_styled_content(text, context),
+        _ => style_set.unordered_list().get_styled_content(text, context),
     }
 }
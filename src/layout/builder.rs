@@ -0,0 +1,347 @@
+use crossterm::style::ContentStyle;
+use unicode_width::UnicodeWidthStr;
+
+use super::{Block, BlockKind, Document, Heading, Line, Span};
+use crate::style::Content;
+
+/// Lays out the styled content produced by the handlers into a [`Document`].
+pub(crate) struct LayoutBuilder {
+    document: Document,
+    line: Line,
+    width: usize,
+    column: usize,
+    pending_space: Vec<Content>,
+    pending_link: Option<String>,
+    /// The word being wrapped, which goes on over contents until a space or line break, with
+    /// the link of each content and what starts the line if the word moves to the next one.
+    word: Vec<(Content, Option<String>)>,
+    word_continuation: Vec<Content>,
+    block: Block,
+    blocks: usize,
+    captures: Vec<(Document, Line, usize)>,
+    link: Option<String>,
+    continued: bool,
+}
+
+impl LayoutBuilder {
+    pub(crate) fn new(width: usize) -> Self {
+        let block = Block {
+            index: 0,
+            kind: BlockKind::Other,
+        };
+        Self {
+            document: Document::default(),
+            line: Line::new(block),
+            width,
+            column: 0,
+            pending_space: vec![],
+            pending_link: None,
+            word: vec![],
+            word_continuation: vec![],
+            block,
+            blocks: 0,
+            captures: vec![],
+            link: None,
+            continued: false,
+        }
+    }
+
+    /// Lays out the rest of a document whose first lines were laid out by another builder.
+    pub(crate) fn continuing(mut self) -> Self {
+        self.continued = true;
+        self
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The width of what was laid out on the current line.
+    pub(crate) fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Starts a new top-level block, which the following lines belong to.
+    pub(crate) fn start_block(&mut self, kind: BlockKind) {
+        self.end_line();
+        self.block = Block {
+            index: self.blocks,
+            kind,
+        };
+        self.blocks += 1;
+        self.line = Line::new(self.block);
+    }
+
+    /// Whether nothing was laid out yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.continued && self.document.lines.is_empty() && self.at_line_start()
+    }
+
+    /// The index of the line being laid out.
+    pub(crate) fn line_index(&self) -> usize {
+        self.document.lines.len()
+    }
+
+    pub(crate) fn push_heading(&mut self, heading: Heading) {
+        self.document.headings.push(heading);
+    }
+
+    /// Makes the following spans hyperlinks to `link`.
+    pub(crate) fn set_link(&mut self, link: Option<&str>) {
+        self.link = link.map(str::to_owned);
+    }
+
+    pub(crate) fn at_line_start(&self) -> bool {
+        self.line.spans().is_empty() && self.word.is_empty()
+    }
+
+    /// Breaks the line unless nothing was pushed to it yet.
+    pub(crate) fn end_line(&mut self) {
+        self.flush_word();
+        if !self.at_line_start() {
+            self.break_line();
+        }
+    }
+
+    /// Breaks the line, even if it is empty.
+    pub(crate) fn new_line(&mut self) {
+        self.flush_word();
+        self.break_line();
+    }
+
+    /// Sends the following lines aside until [`LayoutBuilder::end_capture`] returns them.
+    pub(crate) fn start_capture(&mut self) {
+        self.end_line();
+        let document = std::mem::take(&mut self.document);
+        let line = std::mem::replace(&mut self.line, Line::new(self.block));
+        self.captures.push((document, line, self.column));
+        self.column = 0;
+    }
+
+    pub(crate) fn end_capture(&mut self) -> Vec<Line> {
+        self.end_line();
+        match self.captures.pop() {
+            Some((document, line, column)) => {
+                self.line = line;
+                self.column = column;
+                std::mem::replace(&mut self.document, document).lines
+            }
+            None => vec![],
+        }
+    }
+
+    /// Appends lines laid out elsewhere to the current block.
+    pub(crate) fn push_lines(&mut self, lines: Vec<Line>) {
+        self.end_line();
+        for mut line in lines {
+            line.block = self.block;
+            self.document.lines.push(line);
+        }
+    }
+
+    pub(crate) fn push_content(&mut self, contents: Vec<Content>) {
+        self.flush_word();
+        self.push_after_space(contents);
+    }
+
+    /// Pushes `contents` after the spaces held back, unless they start a new line.
+    fn push_after_space(&mut self, contents: Vec<Content>) {
+        let starts_line = contents
+            .first()
+            .is_some_and(|content| content.text().starts_with('\n'));
+        let pending_space = std::mem::take(&mut self.pending_space);
+        if !starts_line {
+            let link = std::mem::replace(&mut self.link, self.pending_link.take());
+            self.push(pending_space);
+            self.link = link;
+        }
+        self.push(contents);
+    }
+
+    /// Pushes `contents` word by word, breaking the line before any word that would go past
+    /// the width and starting the new line with `continuation`. A word only ends at a space
+    /// or line break, so it can go on in the contents of the next call, which keeps it whole
+    /// across style changes. Spaces are held back until the next word so that wrapped lines
+    /// don't end with them.
+    pub(crate) fn push_wrapped_content(
+        &mut self,
+        contents: Vec<Content>,
+        continuation: Vec<Content>,
+    ) {
+        let continuation_width: usize = continuation
+            .iter()
+            .map(|content| content.text().width())
+            .sum();
+        for content in contents {
+            for chunk in content.split_words() {
+                let text = chunk.text();
+                if text.starts_with('\n') {
+                    self.push_content(vec![chunk]);
+                } else if text.starts_with(' ') {
+                    self.flush_word();
+                    if self.column > continuation_width {
+                        // Spaces belong to a link only when they are inside of it.
+                        self.pending_link = self.link.clone();
+                        self.pending_space.push(chunk);
+                    } else {
+                        let link = self.link.clone();
+                        self.push_word(vec![(chunk, link)], &continuation);
+                    }
+                } else {
+                    self.word.push((chunk, self.link.clone()));
+                    self.word_continuation = continuation.clone();
+                }
+            }
+        }
+    }
+
+    /// Pushes the word being wrapped, if there is one.
+    fn flush_word(&mut self) {
+        if !self.word.is_empty() {
+            let word = std::mem::take(&mut self.word);
+            let continuation = std::mem::take(&mut self.word_continuation);
+            self.push_word(word, &continuation);
+        }
+    }
+
+    /// Pushes the parts of a word with their links, on the next line if the word doesn't fit
+    /// on this one.
+    fn push_word(&mut self, word: Vec<(Content, Option<String>)>, continuation: &[Content]) {
+        let continuation_width: usize = continuation
+            .iter()
+            .map(|content| content.text().width())
+            .sum();
+        let pending_width: usize = self
+            .pending_space
+            .iter()
+            .map(|content| content.text().width())
+            .sum();
+        let width: usize = word.iter().map(|(part, _)| part.text().width()).sum();
+        if self.column + pending_width + width > self.width && self.column > continuation_width {
+            self.break_line();
+            self.push_continuation(continuation);
+        }
+        let long = width > self.width.saturating_sub(continuation_width);
+        let link = self.link.take();
+        for (part, part_link) in word {
+            self.link = part_link;
+            if long {
+                self.push_long_word(part, continuation);
+            } else {
+                self.push_after_space(vec![part]);
+            }
+        }
+        self.link = link;
+    }
+
+    /// Pushes a word wider than a whole line, breaking it wherever the line is full.
+    fn push_long_word(&mut self, word: Content, continuation: &[Content]) {
+        let mut part = String::new();
+        for c in word.text().chars() {
+            if self.column + part.width() + c.to_string().width() > self.width && !part.is_empty() {
+                self.push_after_space(vec![word.with_text(&part)]);
+                self.break_line();
+                self.push_continuation(continuation);
+                part.clear();
+            }
+            part.push(c);
+        }
+        if !part.is_empty() {
+            self.push_after_space(vec![word.with_text(&part)]);
+        }
+    }
+
+    /// Starts a wrapped line, keeping the prefix out of any link.
+    fn push_continuation(&mut self, continuation: &[Content]) {
+        let link = self.link.take();
+        self.push(continuation.to_vec());
+        self.link = link;
+    }
+
+    fn push(&mut self, contents: Vec<Content>) {
+        for content in contents {
+            let style = match &content {
+                Content::String(_) => ContentStyle::new(),
+                Content::StyledContent(content) => *content.style(),
+            };
+            let mut parts = content.text().split('\n');
+            if let Some(first) = parts.next() {
+                self.push_span(first, style);
+            }
+            for part in parts {
+                self.break_line();
+                self.push_span(part, style);
+            }
+        }
+    }
+
+    fn push_span(&mut self, text: &str, style: ContentStyle) {
+        if !text.is_empty() {
+            self.column += text.width();
+            let span = Span::new(text, style);
+            self.line.push(match &self.link {
+                Some(link) => span.with_link(link),
+                None => span,
+            });
+        }
+    }
+
+    fn break_line(&mut self) {
+        self.pending_space.clear();
+        let line = std::mem::replace(&mut self.line, Line::new(self.block));
+        self.document.lines.push(line);
+        self.column = 0;
+    }
+
+    pub(crate) fn build(mut self) -> Document {
+        self.end_line();
+        self.document
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::{Attribute, Stylize};
+
+    use super::*;
+
+    fn wrap(width: usize, contents: Vec<Content>) -> Vec<String> {
+        let mut layout = LayoutBuilder::new(width);
+        for content in contents {
+            layout.push_wrapped_content(vec![content], vec![]);
+        }
+        layout.build().lines().iter().map(Line::text).collect()
+    }
+
+    #[test]
+    fn words_stay_whole_across_style_changes() {
+        let bold = ContentStyle::new().attribute(Attribute::Bold);
+        let cases: [(usize, Vec<&str>, &[&str]); 3] = [
+            (
+                10,
+                vec!["abc defgh", "ij", ", next"],
+                &["abc", "defghij,", "next"],
+            ),
+            (10, vec!["abc ", "defgh", "ij"], &["abc", "defghij"]),
+            (
+                9,
+                vec!["abc defgh", "ijklmn", "op"],
+                &["abc", "defghijkl", "mnop"],
+            ),
+        ];
+        for (width, texts, lines) in cases {
+            let contents = texts
+                .iter()
+                .enumerate()
+                .map(|(index, text)| {
+                    if index % 2 == 0 {
+                        Content::String(text.to_string())
+                    } else {
+                        Content::StyledContent(bold.apply(text.to_string()))
+                    }
+                })
+                .collect();
+            assert_eq!(wrap(width, contents), lines, "wrapping {:?}", texts);
+        }
+    }
+}
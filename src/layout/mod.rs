@@ -0,0 +1,191 @@
+pub(crate) mod builder;
+pub(crate) use builder::LayoutBuilder;
+
+use crossterm::style::ContentStyle;
+use pulldown_cmark::{HeadingLevel, Tag};
+use unicode_width::UnicodeWidthStr;
+
+/// The kind of top-level markdown block a line was rendered from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockKind {
+    Paragraph,
+    Heading(usize),
+    BlockQuote,
+    CodeBlock,
+    List,
+    Table,
+    Rule,
+    Footnotes,
+    References,
+    Other,
+}
+
+impl BlockKind {
+    pub(crate) fn from_tag(tag: &Tag) -> Self {
+        match tag {
+            Tag::Paragraph => BlockKind::Paragraph,
+            Tag::Heading(level, ..) => BlockKind::Heading(heading_number(*level)),
+            Tag::BlockQuote => BlockKind::BlockQuote,
+            Tag::CodeBlock(_) => BlockKind::CodeBlock,
+            Tag::List(_) => BlockKind::List,
+            Tag::Table(_) => BlockKind::Table,
+            _ => BlockKind::Other,
+        }
+    }
+}
+
+/// The number of a heading level, from 1 for `h1` to 6 for `h6`.
+pub(crate) fn heading_number(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// The top-level block a line belongs to, `index` counting blocks from the start of the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub index: usize,
+    pub kind: BlockKind,
+}
+
+/// A run of text sharing one style.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    text: String,
+    style: ContentStyle,
+    link: Option<String>,
+}
+
+impl Span {
+    pub fn new(text: &str, style: ContentStyle) -> Self {
+        Self {
+            text: text.to_owned(),
+            style,
+            link: None,
+        }
+    }
+
+    /// Makes the span a hyperlink to `url`.
+    pub fn with_link(mut self, url: &str) -> Self {
+        self.link = Some(url.to_owned());
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn style(&self) -> &ContentStyle {
+        &self.style
+    }
+
+    pub fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+
+    /// Display width of the span in terminal columns.
+    pub fn width(&self) -> usize {
+        self.text.width()
+    }
+}
+
+/// A rendered line, without its line break.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    spans: Vec<Span>,
+    block: Block,
+}
+
+impl Line {
+    pub(crate) fn new(block: Block) -> Self {
+        Self {
+            spans: vec![],
+            block,
+        }
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    /// The text of the line without any styling.
+    pub fn text(&self) -> String {
+        self.spans.iter().map(Span::text).collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.spans.iter().map(Span::width).sum()
+    }
+
+    pub(crate) fn push(&mut self, span: Span) {
+        match self.spans.last_mut() {
+            Some(last) if last.style == span.style && last.link == span.link => {
+                last.text.push_str(&span.text)
+            }
+            _ => self.spans.push(span),
+        }
+    }
+}
+
+/// A heading of the document, `line` being the index of its first line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heading {
+    level: usize,
+    text: String,
+    line: usize,
+}
+
+impl Heading {
+    pub(crate) fn new(level: usize, text: &str, line: usize) -> Self {
+        Self {
+            level,
+            text: text.to_owned(),
+            line,
+        }
+    }
+
+    /// From 1 for `#` to 6 for `######`.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+/// A rendered markdown document laid out as lines of styled spans.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Document {
+    lines: Vec<Line>,
+    headings: Vec<Heading>,
+}
+
+impl Document {
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Every heading, in document order.
+    pub fn headings(&self) -> &[Heading] {
+        &self.headings
+    }
+
+    /// The text of the document without any styling.
+    pub fn text(&self) -> String {
+        self.lines.iter().map(|line| line.text() + "\n").collect()
+    }
+}
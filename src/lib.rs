@@ -1,25 +1,26 @@
+mod config;
 mod context;
+mod error;
 mod handler;
-mod style;
+pub mod layout;
+mod pager;
+mod renderer;
+mod section;
+pub mod style;
+mod toc;
+mod watch;
 
-use handler::{EventHandler, StdoutHandler};
-use pulldown_cmark::{Options, Parser};
-use std::{fs, path::PathBuf};
-use style::StyleSetBuilder;
+pub use config::{Config, Theme};
+pub use error::{Error, Result};
+pub use layout::Document;
+pub use pager::Pager;
+pub use renderer::Renderer;
+pub use style::{LinkMode, StyleSet, StyleSetBuilder};
+pub use toc::{TableOfContents, TocEntry};
+pub use watch::Watcher;
 
-pub fn print_markdown_file(file: PathBuf) {
-    let file_content = fs::read_to_string(file).unwrap();
+use std::{fs::File, path::PathBuf};
 
-    let mut context = context::Context::default();
-    let mut stdout = StdoutHandler::default();
-
-    let style_set = StyleSetBuilder::new().build();
-
-    let parser = Parser::new_ext(&file_content, Options::empty());
-
-    for event in parser {
-        event.handle(&mut context, &mut stdout, &style_set);
-    }
-
-    stdout.flush();
+pub fn print_markdown_file(file: PathBuf) -> Result<()> {
+    Renderer::default().render_reader(File::open(file)?)
 }
@@ -1,7 +1,460 @@
-use std::path::PathBuf;
+use std::{
+    env,
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    process,
+};
 
-use dear_md::print_markdown_file;
+use clap::{builder::RangedU64ValueParser, ArgEnum, CommandFactory, ErrorKind, Parser};
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    terminal::{Clear, ClearType},
+    tty::IsTty,
+};
+use dear_md::{
+    style::LongLines, Config, Error, LinkMode, Pager, Renderer, Result, TableOfContents, Theme,
+    Watcher,
+};
 
+const THEMES: [&str; 7] = [
+    "base16-ocean.dark",
+    "base16-eighties.dark",
+    "base16-mocha.dark",
+    "base16-ocean.light",
+    "InspiredGitHub",
+    "Solarized (dark)",
+    "Solarized (light)",
+];
+
+/// The document `--list-themes` shows each theme on.
+const SAMPLE: &str = "\
+## A section
+
+Some text with *emphasis*, **strong** words, `inline code`, ~~a mistake~~ and
+[a link](https://example.com).
+
+> A quoted line.
+
+- A list item
+- [x] A finished task
+
+1. A numbered item
+
+```rust
 fn main() {
-    print_markdown_file(PathBuf::from("text"));
+    println!(\"Hello!\");
+}
+```
+
+| Column | Other |
+|--------|-------|
+| Cell   | Cell  |
+
+---
+";
+
+#[derive(Clone, Copy, ArgEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum LongLinesChoice {
+    Wrap,
+    Truncate,
+    Widen,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum LinkChoice {
+    Auto,
+    Hyperlink,
+    Inline,
+    References,
+}
+
+/// Render markdown files in the terminal
+#[derive(Parser)]
+#[clap(name = "dear-md", version)]
+struct Cli {
+    /// Markdown files to render, `-` or no file reads from stdin
+    #[clap(value_parser)]
+    files: Vec<PathBuf>,
+
+    /// Read settings from this config file instead of
+    /// `$XDG_CONFIG_HOME/dear-md/config.toml`, a `.dear-md.toml` in the
+    /// directory of a file or above it up to the repository root takes
+    /// precedence over it, and options given on the command line over both
+    #[clap(long, value_name = "FILE", value_parser)]
+    config: Option<PathBuf>,
+
+    /// Print the config the first file is rendered with, merged from the
+    /// defaults, the user config and the `.dear-md.toml` of its project,
+    /// with the file each value comes from
+    #[clap(long)]
+    print_config: bool,
+
+    /// Syntax highlighting theme used for code blocks, either one of
+    /// the built-in themes or a path to a `.tmTheme` file
+    /// [default: base16-ocean.dark]
+    #[clap(short, long)]
+    theme: Option<String>,
+
+    /// Theme for the styles of everything but code blocks, either one of
+    /// the built-in themes or a file in the `themes` directory next to
+    /// the user config
+    /// [default: dark]
+    #[clap(long, value_name = "NAME")]
+    style_theme: Option<String>,
+
+    /// Show each style theme on a sample document
+    #[clap(long)]
+    list_themes: bool,
+
+    /// Highlight code blocks marked with a language alias as another
+    /// language, given as ALIAS=LANGUAGE
+    #[clap(
+        long = "syntax-alias",
+        value_name = "ALIAS=LANGUAGE",
+        value_parser = parse_syntax_alias
+    )]
+    syntax_aliases: Vec<(String, String)>,
+
+    /// Wrap text at this many columns instead of the terminal width,
+    /// also used as the width of code blocks and rules
+    #[clap(short, long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    width: Option<usize>,
+
+    /// Number of columns between tab stops in code blocks
+    /// [default: 4]
+    #[clap(long, value_name = "N")]
+    tab_width: Option<usize>,
+
+    /// What happens to code lines wider than the block, `widen` makes
+    /// the block as wide as the output when a line doesn't fit
+    /// [default: wrap]
+    #[clap(long, arg_enum)]
+    long_lines: Option<LongLinesChoice>,
+
+    /// Draw a frame around code blocks, with their language in its top
+    /// border
+    #[clap(long)]
+    code_frame: bool,
+
+    /// When to use colors and attributes in the output
+    #[clap(long, arg_enum, default_value = "auto")]
+    color: ColorChoice,
+
+    /// How links are shown, `auto` makes them clickable when writing
+    /// colors to a terminal that supports hyperlinks, `references`
+    /// numbers them and lists their URLs at the end [default: auto]
+    #[clap(long, arg_enum)]
+    links: Option<LinkChoice>,
+
+    /// Render only the section under this heading, nested headings
+    /// separated by `>` as in "Deployment > Rollback"
+    #[clap(short, long)]
+    section: Option<String>,
+
+    /// Print a numbered outline of the headings instead of the document
+    #[clap(long)]
+    toc: bool,
+
+    /// Show the output in a pager when writing to a terminal
+    #[clap(short, long)]
+    pager: bool,
+
+    /// Never show the output in a pager, even when the config enables it
+    #[clap(long, conflicts_with = "pager")]
+    no_pager: bool,
+
+    /// Write each block as soon as it is complete instead of waiting for
+    /// the whole input, for markdown arriving slowly on stdin
+    #[clap(long, conflicts_with_all = &["pager", "toc", "watch", "section"])]
+    stream: bool,
+
+    /// Render again whenever an input file changes
+    #[clap(long, conflicts_with = "output", requires = "files")]
+    watch: bool,
+
+    /// Write the rendered output to a file instead of stdout
+    #[clap(short, long, value_parser)]
+    output: Option<PathBuf>,
+}
+
+/// Splits a `--syntax-alias` argument into the alias and the language.
+fn parse_syntax_alias(alias: &str) -> std::result::Result<(String, String), String> {
+    match alias.split_once('=') {
+        Some((alias, language)) => Ok((alias.to_owned(), language.to_owned())),
+        None => Err("expected ALIAS=LANGUAGE".to_owned()),
+    }
+}
+
+fn read_input(path: &Path) -> Result<String> {
+    let mut markdown = String::new();
+    if path == Path::new("-") {
+        io::stdin().read_to_string(&mut markdown)?;
+    } else {
+        File::open(path)?.read_to_string(&mut markdown)?;
+    }
+    Ok(markdown)
+}
+
+fn render(renderer: &Renderer, cli: &Cli, markdown: &str, output: &mut dyn Write) -> Result<()> {
+    if cli.toc {
+        let document = renderer.render_document(markdown)?;
+        let toc = TableOfContents::new(document.headings());
+        write!(output, "{}", toc).map_err(Error::Terminal)
+    } else {
+        renderer.render_to(markdown, output)
+    }
+}
+
+/// Renders `files`, each with its renderer, every time one of them changes, clearing the
+/// screen before each render. A render that fails, for instance on a half-written file,
+/// leaves the last one on screen.
+fn watch(renderers: &[Renderer], cli: &Cli, files: &[PathBuf]) -> ! {
+    let mut watcher = Watcher::new(files);
+    let mut stdout = io::stdout();
+    loop {
+        let mut buffer: Vec<u8> = vec![];
+        let rendered = files
+            .iter()
+            .zip(renderers)
+            .try_for_each(|(file, renderer)| {
+                read_input(file).and_then(|markdown| render(renderer, cli, &markdown, &mut buffer))
+            });
+        if rendered.is_ok() {
+            let _ = queue!(stdout, Clear(ClearType::All), MoveTo(0, 0));
+            let _ = stdout.write_all(&buffer).and_then(|_| stdout.flush());
+        }
+        watcher.wait();
+    }
+}
+
+/// Terminals known to print OSC 8 escape sequences instead of ignoring them.
+fn supports_hyperlinks() -> bool {
+    !matches!(env::var("TERM").as_deref(), Ok("dumb") | Ok("linux"))
+}
+
+fn exit_with_error(source: impl Display, err: Error) -> ! {
+    eprintln!("dear-md: {}: {}", source, err);
+    match err {
+        Error::UnknownTheme(_) => eprintln!("available themes: {}", THEMES.join(", ")),
+        Error::UnknownStyleTheme(_) => {
+            eprintln!("available style themes: {}", Theme::names().join(", "))
+        }
+        Error::UnknownSection { choices, .. } if !choices.is_empty() => {
+            eprintln!("available sections:");
+            for choice in choices {
+                eprintln!("  {}", choice);
+            }
+        }
+        Error::AmbiguousSection { matches, .. } => {
+            eprintln!("matching sections:");
+            for section in matches {
+                eprintln!("  {}", section);
+            }
+        }
+        _ => (),
+    }
+    process::exit(1);
+}
+
+/// Merges the config of the project `file` is in over the user config, which is the file
+/// given with `--config` or else the one in the config directory when there is one.
+fn load_config(cli: &Cli, file: &Path) -> Config {
+    let user_path = match &cli.config {
+        Some(path) => Some(path.clone()),
+        None => Config::path().filter(|path| path.is_file()),
+    };
+    let dir = match file.parent() {
+        Some(dir) if file != Path::new("-") && !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let project_path = Config::project_path(dir);
+    let config = match Theme::dir() {
+        Some(dir) => Config::default()
+            .theme_dir(&dir)
+            .unwrap_or_else(|err| exit_with_error(dir.display(), err)),
+        None => Config::default(),
+    };
+    let config = user_path
+        .iter()
+        .chain(&project_path)
+        .fold(config, |config, path| {
+            config
+                .merge_file(path)
+                .unwrap_or_else(|err| exit_with_error(path.display(), err))
+        });
+    match &cli.style_theme {
+        Some(name) => config
+            .style_theme(name)
+            .unwrap_or_else(|err| exit_with_error("--style-theme", err)),
+        None => config,
+    }
+}
+
+/// Renders the sample document with each style theme, under a heading with its name.
+fn list_themes(cli: &Cli, output: &mut dyn Write) -> Result<()> {
+    let config = load_config(cli, Path::new("-"));
+    for name in Theme::names() {
+        let config = config.clone().style_theme(&name)?;
+        let markdown = format!("# {}\n\n{}", name, SAMPLE);
+        build_renderer(cli, &config).render_to(&markdown, &mut *output)?;
+        writeln!(output).map_err(Error::Terminal)?;
+    }
+    Ok(())
+}
+
+/// Sets up a renderer with `config`, overridden by the options on the command line.
+fn build_renderer(cli: &Cli, config: &Config) -> Renderer {
+    let width = cli.width.or_else(|| config.width());
+    let block_width = width.unwrap_or(80);
+    let (theme, source) = match &cli.theme {
+        Some(theme) => (theme.as_str(), "--theme"),
+        None => (config.theme(), "theme"),
+    };
+    let mut code_block = config
+        .code_block_style(block_width, theme)
+        .unwrap_or_else(|err| exit_with_error(source, err));
+    if let Some(tab_width) = cli.tab_width {
+        code_block = code_block.tab_width(tab_width);
+    }
+    if cli.code_frame {
+        code_block = code_block.frame(true);
+    }
+    if let Some(long_lines) = cli.long_lines {
+        code_block = code_block.long_lines(match long_lines {
+            LongLinesChoice::Wrap => LongLines::Wrap,
+            LongLinesChoice::Truncate => LongLines::Truncate,
+            LongLinesChoice::Widen => LongLines::Widen,
+        });
+    }
+    for (alias, language) in &cli.syntax_aliases {
+        code_block = code_block.alias(alias, language);
+    }
+
+    let style_set = config.style_set_builder().code_block(code_block).build();
+
+    let color = match cli.color {
+        ColorChoice::Auto => cli.output.is_none() && io::stdout().is_tty(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    let auto_link_mode = if color && supports_hyperlinks() {
+        LinkMode::Hyperlink
+    } else {
+        LinkMode::Inline
+    };
+    let link_mode = match cli.links {
+        Some(LinkChoice::Auto) => auto_link_mode,
+        Some(LinkChoice::Hyperlink) => LinkMode::Hyperlink,
+        Some(LinkChoice::Inline) => LinkMode::Inline,
+        Some(LinkChoice::References) => LinkMode::References,
+        None => config.link_mode().unwrap_or(auto_link_mode),
+    };
+
+    let mut renderer = Renderer::new(style_set).color(color).link_mode(link_mode);
+    if let Some(width) = width {
+        renderer = renderer.width(width);
+    }
+    if let Some(section) = &cli.section {
+        renderer = renderer.section(section);
+    }
+    renderer
+}
+
+/// Whether output goes through the pager, which only makes sense on a terminal.
+fn paging(cli: &Cli, config: &Config) -> bool {
+    (cli.pager || config.pager() && !cli.no_pager)
+        && !cli.toc
+        && cli.output.is_none()
+        && io::stdout().is_tty()
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if cli.watch && cli.files.iter().any(|file| file == Path::new("-")) {
+        Cli::command()
+            .error(ErrorKind::ArgumentConflict, "--watch cannot watch stdin")
+            .exit();
+    }
+
+    let files = if cli.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        cli.files.clone()
+    };
+
+    let mut output: Box<dyn Write> = match &cli.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(err) => exit_with_error(path.display(), Error::Io(err)),
+        },
+        None => Box::new(io::stdout()),
+    };
+
+    if cli.list_themes {
+        list_themes(&cli, &mut output)
+            .and_then(|_| output.flush().map_err(Error::Terminal))
+            .unwrap_or_else(|err| exit_with_error("--list-themes", err));
+        return;
+    }
+
+    if cli.print_config {
+        let config = load_config(&cli, &files[0]);
+        write!(output, "{}", config)
+            .and_then(|_| output.flush())
+            .unwrap_or_else(|err| exit_with_error("--print-config", Error::Terminal(err)));
+        return;
+    }
+
+    let configs: Vec<Config> = files.iter().map(|file| load_config(&cli, file)).collect();
+    let renderers: Vec<Renderer> = configs
+        .iter()
+        .map(|config| build_renderer(&cli, config))
+        .collect();
+
+    if cli.watch && !configs.iter().any(|config| paging(&cli, config)) {
+        watch(&renderers, &cli, &files);
+    }
+
+    if cli.stream {
+        for (file, renderer) in files.iter().zip(&renderers) {
+            let rendered = if file == Path::new("-") {
+                renderer.render_stream(io::stdin(), &mut output)
+            } else {
+                File::open(file)
+                    .map_err(Error::from)
+                    .and_then(|input| renderer.render_stream(input, &mut output))
+            };
+            rendered.unwrap_or_else(|err| exit_with_error(file.display(), err));
+        }
+        return;
+    }
+
+    for ((file, config), renderer) in files.iter().zip(&configs).zip(&renderers) {
+        let title = file.display().to_string();
+        read_input(file)
+            .and_then(|markdown| {
+                if paging(&cli, config) {
+                    let pager = Pager::new(renderer, &markdown, &title)
+                        .case_sensitive(config.case_sensitive());
+                    if cli.watch {
+                        pager.watch(file).run()
+                    } else {
+                        pager.run()
+                    }
+                } else {
+                    render(renderer, &cli, &markdown, &mut output)
+                }
+            })
+            .unwrap_or_else(|err| exit_with_error(title, err));
+    }
 }
@@ -0,0 +1,60 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What a key press asks the pager to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    LineDown,
+    LineUp,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    Top,
+    Bottom,
+    SearchForward,
+    SearchBackward,
+    NextMatch,
+    PreviousMatch,
+    NextHeading,
+    PreviousHeading,
+    NextSection,
+    PreviousSection,
+    Outline,
+    Quit,
+}
+
+impl Command {
+    pub(crate) fn from_key(key: KeyEvent) -> Option<Self> {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let command = match key.code {
+            KeyCode::Char('c') if control => Command::Quit,
+            KeyCode::Char('f') if control => Command::PageDown,
+            KeyCode::Char('b') if control => Command::PageUp,
+            KeyCode::Char('d') if control => Command::HalfPageDown,
+            KeyCode::Char('u') if control => Command::HalfPageUp,
+            KeyCode::Char('e') | KeyCode::Char('n') if control => Command::LineDown,
+            KeyCode::Char('y') | KeyCode::Char('p') if control => Command::LineUp,
+            _ if control => return None,
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => Command::LineDown,
+            KeyCode::Char('k') | KeyCode::Up => Command::LineUp,
+            KeyCode::Char(' ') | KeyCode::Char('f') | KeyCode::PageDown => Command::PageDown,
+            KeyCode::Char('b') | KeyCode::PageUp => Command::PageUp,
+            KeyCode::Char('d') => Command::HalfPageDown,
+            KeyCode::Char('u') => Command::HalfPageUp,
+            KeyCode::Char('g') | KeyCode::Home => Command::Top,
+            KeyCode::Char('G') | KeyCode::End => Command::Bottom,
+            KeyCode::Char('/') => Command::SearchForward,
+            KeyCode::Char('?') => Command::SearchBackward,
+            KeyCode::Char('n') => Command::NextMatch,
+            KeyCode::Char('N') => Command::PreviousMatch,
+            KeyCode::Char(']') => Command::NextHeading,
+            KeyCode::Char('[') => Command::PreviousHeading,
+            KeyCode::Char('}') => Command::NextSection,
+            KeyCode::Char('{') => Command::PreviousSection,
+            KeyCode::Char('t') => Command::Outline,
+            KeyCode::Char('q') | KeyCode::Esc => Command::Quit,
+            _ => return None,
+        };
+        Some(command)
+    }
+}
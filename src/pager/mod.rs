@@ -0,0 +1,572 @@
+pub(crate) mod command;
+pub(crate) use command::Command;
+
+pub(crate) mod search;
+pub(crate) use search::Search;
+
+pub(crate) mod outline;
+pub(crate) use outline::Outline;
+
+use std::{
+    fs,
+    io::{stdout, Stdout, Write},
+    path::{Path, PathBuf},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, KeyCode, KeyEvent, KeyModifiers},
+    queue,
+    style::{Print, PrintStyledContent, Stylize},
+    terminal::{self, ClearType},
+};
+
+use crate::error::{Error, Result};
+use crate::layout::Document;
+use crate::renderer::Renderer;
+use crate::toc::TableOfContents;
+use crate::watch::{Watcher, POLL_INTERVAL};
+
+/// Shows a rendered document one screen at a time on the alternate screen.
+pub struct Pager<'a> {
+    renderer: &'a Renderer,
+    markdown: String,
+    watched: Option<(PathBuf, Watcher)>,
+    title: String,
+    document: Document,
+    top: usize,
+    columns: usize,
+    rows: usize,
+    search: Option<Search>,
+    current_match: Option<usize>,
+    prompt: Option<Prompt>,
+    outline: Option<Outline>,
+    message: Option<String>,
+    case_sensitive: bool,
+}
+
+/// A search pattern being typed on the status line, with where the pager was when typing
+/// started so that each edit searches from there and cancelling goes back to it.
+struct Prompt {
+    backward: bool,
+    input: String,
+    top: usize,
+    search: Option<Search>,
+    current_match: Option<usize>,
+}
+
+impl<'a> Pager<'a> {
+    pub fn new(renderer: &'a Renderer, markdown: &str, title: &str) -> Self {
+        Self {
+            renderer,
+            markdown: markdown.to_owned(),
+            watched: None,
+            title: title.to_owned(),
+            document: Document::default(),
+            top: 0,
+            columns: 0,
+            rows: 0,
+            search: None,
+            current_match: None,
+            prompt: None,
+            outline: None,
+            message: None,
+            case_sensitive: false,
+        }
+    }
+
+    /// Makes searches match the case of the pattern, which they ignore by default.
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Reloads the document from `path` whenever the file changes, keeping the scroll
+    /// position.
+    pub fn watch(mut self, path: &Path) -> Self {
+        self.watched = Some((path.to_owned(), Watcher::new(&[path])));
+        self
+    }
+
+    /// Pages through the document until the user quits.
+    pub fn run(mut self) -> Result<()> {
+        let (columns, rows) = terminal::size().map_err(Error::Terminal)?;
+        self.resize(columns, rows)?;
+
+        let mut screen = Screen::enter()?;
+        loop {
+            self.draw(&mut screen.stdout)?;
+            if self.watched.is_some() && !event::poll(POLL_INTERVAL).map_err(Error::Terminal)? {
+                self.reload_if_changed();
+                continue;
+            }
+            match event::read().map_err(Error::Terminal)? {
+                event::Event::Key(key) if self.prompt.is_some() => self.edit_prompt(key),
+                event::Event::Key(key) if self.outline.is_some() => self.use_outline(key),
+                event::Event::Key(key) => {
+                    self.message = None;
+                    match Command::from_key(key) {
+                        Some(Command::Quit) => break,
+                        Some(command) => self.apply(command),
+                        None => (),
+                    }
+                }
+                event::Event::Resize(columns, rows) => self.resize(columns, rows)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Lines of the document that fit on the screen, the last row holding the status line.
+    fn page_height(&self) -> usize {
+        self.rows.saturating_sub(1).max(1)
+    }
+
+    fn max_top(&self) -> usize {
+        self.document
+            .lines()
+            .len()
+            .saturating_sub(self.page_height())
+    }
+
+    fn apply(&mut self, command: Command) {
+        let page = self.page_height();
+        let top = match command {
+            Command::SearchForward | Command::SearchBackward => {
+                self.prompt = Some(Prompt {
+                    backward: command == Command::SearchBackward,
+                    input: String::new(),
+                    top: self.top,
+                    search: self.search.clone(),
+                    current_match: self.current_match,
+                });
+                self.top
+            }
+            Command::NextMatch | Command::PreviousMatch => {
+                match self.search.as_ref().map(Search::backward) {
+                    Some(backward) => {
+                        self.jump_to_match(backward == (command == Command::PreviousMatch))
+                    }
+                    None => self.message = Some("no previous search".to_owned()),
+                }
+                self.top
+            }
+            Command::NextHeading | Command::NextSection => {
+                let level = self.section_level(command == Command::NextSection);
+                let heading = self
+                    .document
+                    .headings()
+                    .iter()
+                    .find(|heading| heading.line() > self.top && heading.level() <= level);
+                match heading {
+                    Some(heading) => heading.line(),
+                    None => {
+                        self.message = Some("no next heading".to_owned());
+                        self.top
+                    }
+                }
+            }
+            Command::PreviousHeading | Command::PreviousSection => {
+                let level = self.section_level(command == Command::PreviousSection);
+                let heading = self
+                    .document
+                    .headings()
+                    .iter()
+                    .rev()
+                    .find(|heading| heading.line() < self.top && heading.level() <= level);
+                match heading {
+                    Some(heading) => heading.line(),
+                    None => {
+                        self.message = Some("no previous heading".to_owned());
+                        self.top
+                    }
+                }
+            }
+            Command::Outline => {
+                let toc = TableOfContents::new(self.document.headings());
+                if toc.is_empty() {
+                    self.message = Some("no headings".to_owned());
+                } else {
+                    let current = self.current_heading().unwrap_or(0);
+                    self.outline = Some(Outline::new(toc, current));
+                }
+                self.top
+            }
+            Command::LineDown => self.top + 1,
+            Command::LineUp => self.top.saturating_sub(1),
+            Command::PageDown => self.top + page,
+            Command::PageUp => self.top.saturating_sub(page),
+            Command::HalfPageDown => self.top + page / 2,
+            Command::HalfPageUp => self.top.saturating_sub(page / 2),
+            Command::Top => 0,
+            Command::Bottom => self.max_top(),
+            Command::Quit => self.top,
+        };
+        self.top = top.min(self.max_top());
+    }
+
+    /// The index of the heading of the section at the top of the screen.
+    fn current_heading(&self) -> Option<usize> {
+        self.document
+            .headings()
+            .iter()
+            .rposition(|heading| heading.line() <= self.top)
+    }
+
+    /// The deepest heading level a heading jump stops at, which is the level of the current
+    /// section when jumping between sections and any level otherwise.
+    fn section_level(&self, sections: bool) -> usize {
+        let current = self
+            .current_heading()
+            .map(|index| self.document.headings()[index].level());
+        match current {
+            Some(level) if sections => level,
+            _ => 6,
+        }
+    }
+
+    fn use_outline(&mut self, key: KeyEvent) {
+        let page = self.page_height() as isize;
+        let outline = match &mut self.outline {
+            Some(outline) => outline,
+            None => return,
+        };
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => outline.move_by(1),
+            KeyCode::Char('k') | KeyCode::Up => outline.move_by(-1),
+            KeyCode::PageDown => outline.move_by(page),
+            KeyCode::PageUp => outline.move_by(-page),
+            KeyCode::Char('g') | KeyCode::Home => outline.select_first(),
+            KeyCode::Char('G') | KeyCode::End => outline.select_last(),
+            KeyCode::Enter => {
+                if let Some(line) = outline.selected_line() {
+                    self.top = line.min(self.max_top());
+                }
+                self.outline = None;
+            }
+            KeyCode::Char('t') | KeyCode::Char('q') | KeyCode::Esc => self.outline = None,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.outline = None
+            }
+            _ => (),
+        }
+    }
+
+    fn edit_prompt(&mut self, key: KeyEvent) {
+        let prompt = match &mut self.prompt {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(prompt) = self.close_prompt() {
+                    self.start_search(&prompt.input, prompt.backward);
+                }
+            }
+            KeyCode::Esc => {
+                self.close_prompt();
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.close_prompt();
+            }
+            KeyCode::Backspace if prompt.input.is_empty() => {
+                self.close_prompt();
+            }
+            KeyCode::Backspace => {
+                prompt.input.pop();
+                self.search_as_typed();
+            }
+            KeyCode::Char(c) => {
+                prompt.input.push(c);
+                self.search_as_typed();
+            }
+            _ => (),
+        }
+    }
+
+    /// Closes the prompt and goes back to where the pager was when it was opened.
+    fn close_prompt(&mut self) -> Option<Prompt> {
+        self.restore_from_prompt();
+        self.prompt.take()
+    }
+
+    /// Goes back to where the pager was when the prompt was opened. The document may have
+    /// been laid out again since, so matches are found again.
+    fn restore_from_prompt(&mut self) {
+        let prompt = match &self.prompt {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        self.search = prompt.search.clone();
+        self.current_match = prompt.current_match;
+        self.top = prompt.top.min(self.max_top());
+        if let Some(search) = &mut self.search {
+            search.find(&self.document);
+        }
+    }
+
+    /// Searches for what has been typed so far from where the prompt was opened, keeping
+    /// the last matches while the pattern is not a valid regex yet.
+    fn search_as_typed(&mut self) {
+        let (pattern, backward) = match &self.prompt {
+            Some(prompt) => (prompt.input.clone(), prompt.backward),
+            None => return,
+        };
+        if pattern.is_empty() {
+            self.restore_from_prompt();
+            return;
+        }
+        if let Ok(mut search) = Search::new(&pattern, backward, self.case_sensitive) {
+            self.restore_from_prompt();
+            search.find(&self.document);
+            self.search = Some(search);
+            self.current_match = None;
+            self.jump_to_match(!backward);
+            self.message = None;
+        }
+    }
+
+    /// Searches for `pattern`, or for the previous pattern again when it is empty.
+    fn start_search(&mut self, pattern: &str, backward: bool) {
+        let pattern = match (pattern, &self.search) {
+            ("", Some(search)) => search.pattern().to_owned(),
+            ("", None) => return,
+            _ => pattern.to_owned(),
+        };
+        match Search::new(&pattern, backward, self.case_sensitive) {
+            Ok(mut search) => {
+                search.find(&self.document);
+                self.search = Some(search);
+                self.current_match = None;
+                self.jump_to_match(!backward);
+            }
+            Err(err) => {
+                let reason = err.to_string();
+                self.message = Some(format!(
+                    "invalid pattern: {}",
+                    reason
+                        .lines()
+                        .last()
+                        .unwrap_or_default()
+                        .trim_start_matches("error: ")
+                ));
+            }
+        }
+    }
+
+    /// Moves to the next match in the given direction, scrolling it into view.
+    fn jump_to_match(&mut self, forward: bool) {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return,
+        };
+        match search.next(self.current_match, self.top, forward) {
+            Some(index) => {
+                let line = search.matches()[index].line;
+                self.current_match = Some(index);
+                if line < self.top || line >= self.top + self.page_height() {
+                    self.top = line.min(self.max_top());
+                }
+            }
+            None if search.matches().is_empty() => {
+                self.message = Some(format!("pattern not found: {}", search.pattern()))
+            }
+            None => self.message = Some("no more matches".to_owned()),
+        }
+    }
+
+    /// Reloads the watched file once it changed. A file that can't be read or rendered
+    /// leaves the last document on screen.
+    fn reload_if_changed(&mut self) {
+        let path = match &mut self.watched {
+            Some((path, watcher)) => {
+                if !watcher.changed() {
+                    return;
+                }
+                path.clone()
+            }
+            None => return,
+        };
+        let reloaded = fs::read_to_string(&path)
+            .map_err(Error::from)
+            .and_then(|markdown| {
+                let document = self
+                    .renderer
+                    .layout(&markdown, self.renderer.layout_width(self.columns))?;
+                Ok((markdown, document))
+            });
+        match reloaded {
+            Ok((markdown, document)) => {
+                self.markdown = markdown;
+                self.document = document;
+                self.top = self.top.min(self.max_top());
+                if let Some(search) = &mut self.search {
+                    search.find(&self.document);
+                    self.current_match = None;
+                }
+                self.message = None;
+            }
+            Err(err) => self.message = Some(format!("could not reload: {}", err)),
+        }
+    }
+
+    /// Lays the document out again for the new terminal size, keeping the block at the top
+    /// of the screen in view.
+    fn resize(&mut self, columns: u16, rows: u16) -> Result<()> {
+        let anchor = self
+            .document
+            .lines()
+            .get(self.top)
+            .map(|line| line.block().index);
+        self.columns = columns as usize;
+        self.rows = rows as usize;
+        self.document = self
+            .renderer
+            .layout(&self.markdown, self.renderer.layout_width(self.columns))?;
+        self.top = anchor
+            .and_then(|index| {
+                self.document
+                    .lines()
+                    .iter()
+                    .position(|line| line.block().index == index)
+            })
+            .unwrap_or(0)
+            .min(self.max_top());
+        if let Some(search) = &mut self.search {
+            search.find(&self.document);
+            self.current_match = None;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, stdout: &mut Stdout) -> Result<()> {
+        if self.outline.is_some() {
+            return self.draw_outline(stdout);
+        }
+        let lines = self.document.lines();
+        let end = (self.top + self.page_height()).min(lines.len());
+        let mut output = self.renderer.output(&mut *stdout);
+        for (row, line) in lines[self.top..end].iter().enumerate() {
+            queue!(
+                output.writer(),
+                cursor::MoveTo(0, row as u16),
+                terminal::Clear(ClearType::CurrentLine)
+            )
+            .map_err(Error::Terminal)?;
+            match &self.search {
+                Some(search) => output.queue_line(&search.highlight(self.top + row, line))?,
+                None => output.queue_line(line)?,
+            }
+        }
+        for row in end - self.top..self.page_height() {
+            queue!(
+                output.writer(),
+                cursor::MoveTo(0, row as u16),
+                terminal::Clear(ClearType::CurrentLine),
+                Print("~")
+            )
+            .map_err(Error::Terminal)?;
+        }
+        queue!(
+            output.writer(),
+            cursor::MoveTo(0, self.page_height() as u16),
+            terminal::Clear(ClearType::CurrentLine),
+            PrintStyledContent(self.status().reverse())
+        )
+        .map_err(Error::Terminal)?;
+        output.flush()
+    }
+
+    fn draw_outline(&mut self, stdout: &mut Stdout) -> Result<()> {
+        let height = self.page_height();
+        let rows = match &mut self.outline {
+            Some(outline) => outline.rows(height),
+            None => return Ok(()),
+        };
+        for row in 0..height {
+            queue!(
+                stdout,
+                cursor::MoveTo(0, row as u16),
+                terminal::Clear(ClearType::CurrentLine)
+            )
+            .map_err(Error::Terminal)?;
+            if let Some((text, selected)) = rows.get(row) {
+                let text: String = text.chars().take(self.columns).collect();
+                if *selected {
+                    queue!(stdout, PrintStyledContent(text.reverse()))
+                } else {
+                    queue!(stdout, Print(text))
+                }
+                .map_err(Error::Terminal)?;
+            }
+        }
+        let status: String = " table of contents  (enter to jump, esc to close)"
+            .chars()
+            .take(self.columns)
+            .collect();
+        queue!(
+            stdout,
+            cursor::MoveTo(0, height as u16),
+            terminal::Clear(ClearType::CurrentLine),
+            PrintStyledContent(status.reverse())
+        )
+        .map_err(Error::Terminal)?;
+        stdout.flush().map_err(Error::Terminal)
+    }
+
+    fn status(&self) -> String {
+        if let Some(prompt) = &self.prompt {
+            let prefix = if prompt.backward { '?' } else { '/' };
+            return format!("{}{}", prefix, prompt.input);
+        }
+        if let Some(message) = &self.message {
+            return format!(" {}", message);
+        }
+        let total = self.document.lines().len();
+        let end = (self.top + self.page_height()).min(total);
+        let position = (end * 100).checked_div(total).unwrap_or(100);
+        let status = format!(
+            " {}  lines {}-{}/{}  {}%  (q to quit)",
+            self.title,
+            (self.top + 1).min(total),
+            end,
+            total,
+            position
+        );
+        status.chars().take(self.columns).collect()
+    }
+}
+
+/// The alternate screen in raw mode, restored when dropped.
+struct Screen {
+    stdout: Stdout,
+}
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        let mut stdout = stdout();
+        terminal::enable_raw_mode().map_err(Error::Terminal)?;
+        queue!(
+            stdout,
+            terminal::EnterAlternateScreen,
+            terminal::DisableLineWrap,
+            cursor::Hide
+        )
+        .map_err(Error::Terminal)?;
+        Ok(Self { stdout })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = queue!(
+            self.stdout,
+            cursor::Show,
+            terminal::EnableLineWrap,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = self.stdout.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
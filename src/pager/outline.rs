@@ -0,0 +1,66 @@
+use crate::toc::TableOfContents;
+
+/// The table of contents shown over the document, with one entry selected.
+pub(crate) struct Outline {
+    toc: TableOfContents,
+    selected: usize,
+    offset: usize,
+}
+
+impl Outline {
+    pub(crate) fn new(toc: TableOfContents, selected: usize) -> Self {
+        Self {
+            toc,
+            selected,
+            offset: 0,
+        }
+    }
+
+    /// Moves the selection by `delta` entries, stopping at the first and last one.
+    pub(crate) fn move_by(&mut self, delta: isize) {
+        let last = self.toc.entries().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    pub(crate) fn select_first(&mut self) {
+        self.selected = 0;
+    }
+
+    pub(crate) fn select_last(&mut self) {
+        self.selected = self.toc.entries().len().saturating_sub(1);
+    }
+
+    /// The line of the document the selected heading starts on.
+    pub(crate) fn selected_line(&self) -> Option<usize> {
+        self.toc
+            .entries()
+            .get(self.selected)
+            .map(|entry| entry.line())
+    }
+
+    /// The rows that fit in `height`, scrolled so that the selected entry is visible,
+    /// each with whether it is the selected one.
+    pub(crate) fn rows(&mut self, height: usize) -> Vec<(String, bool)> {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+        self.toc
+            .entries()
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(height)
+            .map(|(index, entry)| {
+                let row = format!(
+                    " {}{} {}",
+                    "   ".repeat(entry.depth()),
+                    entry.number(),
+                    entry.text()
+                );
+                (row, index == self.selected)
+            })
+            .collect()
+    }
+}
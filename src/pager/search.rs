@@ -0,0 +1,127 @@
+use crossterm::style::{Attribute, Stylize};
+use regex::{Regex, RegexBuilder};
+
+use crate::layout::{Document, Line, Span};
+
+/// Where a search pattern matched, as a byte range of a line's text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Match {
+    pub(crate) line: usize,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// A regex search over the rendered text of a document.
+#[derive(Clone)]
+pub(crate) struct Search {
+    regex: Regex,
+    backward: bool,
+    matches: Vec<Match>,
+}
+
+impl Search {
+    pub(crate) fn new(
+        pattern: &str,
+        backward: bool,
+        case_sensitive: bool,
+    ) -> Result<Self, regex::Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()?;
+        Ok(Self {
+            regex,
+            backward,
+            matches: vec![],
+        })
+    }
+
+    pub(crate) fn pattern(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// Whether the search was started with `?`, which reverses `n` and `N`.
+    pub(crate) fn backward(&self) -> bool {
+        self.backward
+    }
+
+    pub(crate) fn matches(&self) -> &[Match] {
+        &self.matches
+    }
+
+    /// Finds every match in `document`, which has to be done again whenever it is laid out.
+    pub(crate) fn find(&mut self, document: &Document) {
+        self.matches = document
+            .lines()
+            .iter()
+            .enumerate()
+            .flat_map(|(index, line)| {
+                self.regex
+                    .find_iter(&line.text())
+                    .filter(|found| !found.as_str().is_empty())
+                    .map(|found| Match {
+                        line: index,
+                        start: found.start(),
+                        end: found.end(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+
+    /// The first match after `current`, or on or after the line `from` when there is none,
+    /// searching the other way when `forward` is false.
+    pub(crate) fn next(&self, current: Option<usize>, from: usize, forward: bool) -> Option<usize> {
+        match (current, forward) {
+            (Some(current), true) => Some(current + 1).filter(|next| *next < self.matches.len()),
+            (Some(current), false) => current.checked_sub(1),
+            (None, true) => self.matches.iter().position(|found| found.line >= from),
+            (None, false) => self.matches.iter().rposition(|found| found.line <= from),
+        }
+    }
+
+    /// `line` with every match on it shown in reverse video.
+    pub(crate) fn highlight(&self, index: usize, line: &Line) -> Line {
+        let ranges: Vec<(usize, usize)> = self
+            .matches
+            .iter()
+            .filter(|found| found.line == index)
+            .map(|found| (found.start, found.end))
+            .collect();
+        if ranges.is_empty() {
+            return line.clone();
+        }
+
+        let mut highlighted = Line::new(*line.block());
+        let mut offset = 0;
+        for span in line.spans() {
+            let end = offset + span.text().len();
+            let mut cuts = vec![offset, end];
+            for (start, stop) in &ranges {
+                cuts.extend(
+                    [*start, *stop]
+                        .iter()
+                        .filter(|cut| (offset..end).contains(*cut)),
+                );
+            }
+            cuts.sort_unstable();
+            cuts.dedup();
+            for part in cuts.windows(2) {
+                let text = &span.text()[part[0] - offset..part[1] - offset];
+                let mut style = *span.style();
+                if ranges
+                    .iter()
+                    .any(|(start, stop)| *start <= part[0] && part[1] <= *stop)
+                {
+                    style = style.attribute(Attribute::Reverse);
+                }
+                let part = Span::new(text, style);
+                highlighted.push(match span.link() {
+                    Some(link) => part.with_link(link),
+                    None => part,
+                });
+            }
+            offset = end;
+        }
+        highlighted
+    }
+}
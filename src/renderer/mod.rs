@@ -0,0 +1,168 @@
+mod stream;
+
+use std::{
+    io::{stdout, Read, Write},
+    ops::Range,
+};
+
+use crossterm::terminal;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+use crate::context::Context;
+use crate::error::Result;
+use crate::handler::{self, EventHandler, OutputHandler};
+use crate::layout::{Document, LayoutBuilder};
+use crate::section;
+use crate::style::{LinkMode, StyleSet, StyleSetBuilder};
+
+/// Renders markdown with a [`StyleSet`].
+pub struct Renderer {
+    style_set: StyleSet,
+    color: bool,
+    width: Option<usize>,
+    link_mode: LinkMode,
+    section: Option<String>,
+}
+
+impl Renderer {
+    pub fn new(style_set: StyleSet) -> Self {
+        Self {
+            style_set,
+            color: true,
+            width: None,
+            link_mode: LinkMode::default(),
+            section: None,
+        }
+    }
+
+    /// Enables or disables colors and attributes in the output.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Wraps text at `width` columns instead of the terminal width.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Chooses how links are shown, see [`LinkMode`].
+    pub fn link_mode(mut self, link_mode: LinkMode) -> Self {
+        self.link_mode = link_mode;
+        self
+    }
+
+    /// Renders only the section under the heading matching `path`, such as
+    /// `Deployment > Rollback`, failing when no heading or several headings match.
+    pub fn section(mut self, path: &str) -> Self {
+        self.section = Some(path.to_owned());
+        self
+    }
+
+    /// Renders `markdown` to stdout.
+    pub fn render_str(&self, markdown: &str) -> Result<()> {
+        self.render_to(markdown, stdout().lock())
+    }
+
+    /// Reads all of `reader` and renders it to stdout.
+    pub fn render_reader<R: Read>(&self, reader: R) -> Result<()> {
+        self.render_reader_to(reader, stdout().lock())
+    }
+
+    /// Reads all of `reader` and renders it into `writer`.
+    pub fn render_reader_to<R: Read, W: Write>(&self, mut reader: R, writer: W) -> Result<()> {
+        let mut markdown = String::new();
+        reader.read_to_string(&mut markdown)?;
+        self.render_to(&markdown, writer)
+    }
+
+    /// Renders `markdown` into a string, escape sequences included.
+    pub fn render_to_string(&self, markdown: &str) -> Result<String> {
+        let mut buffer: Vec<u8> = vec![];
+        self.render_to(markdown, &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    /// Renders `markdown` into any [`Write`] sink.
+    pub fn render_to<W: Write>(&self, markdown: &str, writer: W) -> Result<()> {
+        let document = self.render_document(markdown)?;
+
+        let mut output = self.output(writer);
+        output.queue_document(&document)?;
+        output.flush()
+    }
+
+    /// Lays `markdown` out into a [`Document`] without writing it anywhere.
+    pub fn render_document(&self, markdown: &str) -> Result<Document> {
+        self.layout(markdown, self.width.unwrap_or_else(terminal_width))
+    }
+
+    /// The width text is wrapped at on a terminal `columns` wide.
+    pub(crate) fn layout_width(&self, columns: usize) -> usize {
+        self.width.unwrap_or(columns)
+    }
+
+    pub(crate) fn output<W: Write>(&self, writer: W) -> OutputHandler<W> {
+        OutputHandler::new(writer, self.color)
+    }
+
+    /// Lays `markdown` out into a [`Document`] wrapped at `width` columns.
+    pub(crate) fn layout(&self, markdown: &str, width: usize) -> Result<Document> {
+        let mut context = Context::default();
+        context.set_link_mode(self.link_mode);
+        let mut layout = LayoutBuilder::new(width);
+
+        let (range, footnotes) = match &self.section {
+            Some(path) => {
+                let range = section::find(markdown, path)?;
+                let footnotes = section::footnote_references(markdown, &range);
+                (range, footnotes)
+            }
+            None => (0..markdown.len(), vec![]),
+        };
+        let parser = Parser::new_ext(markdown, options()).into_offset_iter();
+
+        // With a section, footnote definitions are laid out when the section references
+        // them, wherever they are written.
+        let mut definition: Option<(Range<usize>, bool)> = None;
+        for (event, offset) in parser {
+            if let Event::Start(Tag::FootnoteDefinition(label)) = &event {
+                if self.section.is_some() {
+                    let referenced = footnotes.iter().any(|footnote| footnote == label.as_ref());
+                    definition = Some((offset.clone(), referenced));
+                }
+            }
+            let included = match &definition {
+                Some((definition, referenced)) if definition.contains(&offset.start) => *referenced,
+                _ => range.contains(&offset.start),
+            };
+            if included {
+                event.handle(&mut context, &mut layout, &self.style_set)?;
+            }
+        }
+        handler::finish(&mut context, &mut layout, &self.style_set);
+
+        Ok(layout.build())
+    }
+}
+
+/// The markdown extensions every document is parsed with.
+pub(crate) fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+}
+
+fn terminal_width() -> usize {
+    terminal::size()
+        .map(|(columns, _)| columns as usize)
+        .unwrap_or(80)
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new(StyleSetBuilder::new().build())
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+
+use pulldown_cmark::{BrokenLink, Event, Parser};
+
+use super::{options, terminal_width, Renderer};
+use crate::context::Context;
+use crate::error::Result;
+use crate::handler::{self, EventHandler};
+use crate::layout::{Document, LayoutBuilder};
+
+/// How far the input is written, with what the written blocks leave for the next ones.
+struct Written {
+    /// Where the first block that isn't written yet starts in the input.
+    end: usize,
+    lines: usize,
+    context: Context<'static>,
+    /// The link reference definitions of the written blocks, by normalized label, with
+    /// their destination and title.
+    definitions: HashMap<String, (String, String)>,
+}
+
+impl Renderer {
+    /// Renders markdown arriving slowly on `reader`, such as piped program output, writing
+    /// every top-level block as soon as the next one starts. The last block is held back
+    /// until the end of the input, as more text could still change how it looks, and so is
+    /// a block with a link reference that isn't defined yet, along with the blocks after it.
+    /// With a section, which the input may not have reached yet, the whole input is rendered
+    /// once it ends.
+    pub fn render_stream<R: Read, W: Write>(&self, mut reader: R, writer: W) -> Result<()> {
+        if self.section.is_some() {
+            return self.render_reader_to(reader, writer);
+        }
+        let width = self.width.unwrap_or_else(terminal_width);
+        let mut output = self.output(writer);
+        let mut input: Vec<u8> = vec![];
+        let mut buffer = [0; 8192];
+        let mut context = Context::default();
+        context.set_link_mode(self.link_mode);
+        let mut written = Written {
+            end: 0,
+            lines: 0,
+            context,
+            definitions: HashMap::new(),
+        };
+
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            input.extend_from_slice(&buffer[..read]);
+            // Blocks only end at line breaks.
+            if !buffer[..read].contains(&b'\n') {
+                continue;
+            }
+            let markdown = match std::str::from_utf8(&input) {
+                Ok(markdown) => markdown,
+                Err(err) => std::str::from_utf8(&input[..err.valid_up_to()]).unwrap_or_default(),
+            };
+            let (document, next) = self.layout_rest(markdown, width, &written, false)?;
+            if let Some(next) = next {
+                for line in &document.lines()[..next.lines - written.lines] {
+                    output.queue_line(line)?;
+                    output.queue_new_line()?;
+                }
+                output.flush()?;
+                written = next;
+            }
+        }
+
+        let markdown = String::from_utf8_lossy(&input);
+        let (document, _) = self.layout_rest(&markdown, width, &written, true)?;
+        for line in document.lines() {
+            output.queue_line(line)?;
+            output.queue_new_line()?;
+        }
+        output.flush()
+    }
+
+    /// Lays out `markdown` from the first block that isn't `written` yet, parsing only that
+    /// part of it. Once the input has `ended`, what is collected over the whole document is
+    /// appended. Otherwise, the blocks that can't change anymore are returned as written, if
+    /// there are any.
+    fn layout_rest(
+        &self,
+        markdown: &str,
+        width: usize,
+        written: &Written,
+        ended: bool,
+    ) -> Result<(Document, Option<Written>)> {
+        let rest = &markdown[written.end..];
+        let mut context = written.context.between_blocks();
+        let mut layout = LayoutBuilder::new(width);
+        if written.lines > 0 {
+            layout = layout.continuing();
+        }
+
+        // The start of each top-level block in `rest`, with the line it starts on and the
+        // state the blocks before it leave.
+        let mut starts: Vec<(usize, usize, Context<'static>)> = vec![];
+        let mut undefined: Vec<usize> = vec![];
+        let mut definitions: Vec<(usize, String, (String, String))> = vec![];
+        {
+            // Definitions in the written blocks aren't part of `rest`.
+            let mut resolve =
+                |link: BrokenLink| match written.definitions.get(&normalize_label(&link.reference))
+                {
+                    Some((destination, title)) => {
+                        Some((destination.clone().into(), title.clone().into()))
+                    }
+                    None => {
+                        undefined.push(link.span.start);
+                        None
+                    }
+                };
+            let mut parser =
+                Parser::new_with_broken_link_callback(rest, options(), Some(&mut resolve))
+                    .into_offset_iter();
+            let mut depth = 0;
+            for (event, offset) in &mut parser {
+                match &event {
+                    Event::Start(_) | Event::Rule if depth == 0 => {
+                        // Blocks start after their indentation, which is part of them.
+                        let start = rest[..offset.start].rfind('\n').map_or(0, |end| end + 1);
+                        starts.push((start, layout.line_index(), context.between_blocks()));
+                    }
+                    _ => (),
+                }
+                match &event {
+                    Event::Start(_) => depth += 1,
+                    Event::End(_) => depth -= 1,
+                    _ => (),
+                }
+                event.handle(&mut context, &mut layout, &self.style_set)?;
+            }
+            for (label, definition) in parser.reference_definitions().iter() {
+                let title = definition.title.as_deref().unwrap_or_default();
+                definitions.push((
+                    definition.span.start,
+                    normalize_label(label),
+                    (definition.dest.to_string(), title.to_owned()),
+                ));
+            }
+        }
+
+        if ended {
+            handler::finish(&mut context, &mut layout, &self.style_set);
+            return Ok((layout.build(), None));
+        }
+
+        // A definition later in the input could still turn an undefined reference into a
+        // link, so the block with the first one is held back too.
+        let held = match undefined.iter().min() {
+            Some(first) => starts
+                .iter()
+                .rposition(|(start, ..)| start <= first)
+                .unwrap_or(0),
+            None => starts.len().saturating_sub(1),
+        };
+        let document = layout.build();
+        let next = match starts.into_iter().nth(held) {
+            Some((start, line, context)) if line > 0 || start > 0 => {
+                let mut next = Written {
+                    end: written.end + start,
+                    lines: written.lines + line,
+                    context,
+                    definitions: written.definitions.clone(),
+                };
+                for (offset, label, definition) in definitions {
+                    if offset < start {
+                        next.definitions.entry(label).or_insert(definition);
+                    }
+                }
+                Some(next)
+            }
+            _ => None,
+        };
+        Ok((document, next))
+    }
+}
+
+/// A link label as it is matched against definitions: case-insensitive, with any run of
+/// whitespace standing for a single space.
+fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::VecDeque, io, rc::Rc};
+
+    use super::*;
+    use crate::style::{LinkMode, StyleSetBuilder};
+
+    /// Hands out its input a few bytes at a time, and records what was written by then.
+    struct Chunks {
+        chunks: VecDeque<Vec<u8>>,
+        output: Rc<RefCell<Vec<u8>>>,
+        written: Vec<String>,
+    }
+
+    impl Chunks {
+        fn new(markdown: &str, size: usize, output: Rc<RefCell<Vec<u8>>>) -> Self {
+            Self {
+                chunks: markdown
+                    .as_bytes()
+                    .chunks(size)
+                    .map(<[u8]>::to_vec)
+                    .collect(),
+                output,
+                written: vec![],
+            }
+        }
+    }
+
+    impl Read for &mut Chunks {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let written = String::from_utf8_lossy(&self.output.borrow()).into_owned();
+            self.written.push(written);
+            match self.chunks.pop_front() {
+                Some(chunk) => {
+                    buffer[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buffer)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn renderer(link_mode: LinkMode) -> Renderer {
+        Renderer::new(StyleSetBuilder::new().build())
+            .width(30)
+            .link_mode(link_mode)
+    }
+
+    /// Streams `markdown` in chunks of `size` bytes, returning the output and what was
+    /// written before each read.
+    fn stream(renderer: &Renderer, markdown: &str, size: usize) -> (String, Vec<String>) {
+        let output = Rc::new(RefCell::new(vec![]));
+        let mut chunks = Chunks::new(markdown, size, output.clone());
+        renderer
+            .render_stream(&mut chunks, Shared(output.clone()))
+            .expect("rendered");
+        let output = String::from_utf8_lossy(&output.borrow()).into_owned();
+        (output, chunks.written)
+    }
+
+    const CASES: [(&str, &str); 6] = [
+        (
+            "unclosed code fence",
+            "Some text.\n\n```rust\nfn main() {}\n\nlet a = 1;\n",
+        ),
+        (
+            "setext headings",
+            "A title\n=======\n\nA paragraph\nand more.\n\nNot a rule\n---\n\nEnd.\n",
+        ),
+        (
+            "references before definitions",
+            "See [one] and [two][].\n\nThen [Three].\n\n[one]: https://one.test\n\
+             [two]: https://two.test \"Two\"\n\nEnd with [one] and [three].\n\n\
+             [three]: https://three.test\n",
+        ),
+        (
+            "footnotes",
+            "Text[^1] and more[^note].\n\n[^1]: The first.\n\nMiddle.\n\n\
+             [^note]: A named one\n    going on.\n\nEnd[^1].\n",
+        ),
+        (
+            "nested blocks",
+            "- one\n- two\n  lazy\n\n  > quoted\n  > ***\n\n1. first\n\n| a | b |\n|---|---|\n\
+             | 1 | 2 |\n\n    indented\n    code\n\nü ö ä wrapped words over the width\n",
+        ),
+        (
+            "inline links",
+            "[a](https://a.test) and <https://b.test>.\n\n---\n\n[c](https://c.test)\n",
+        ),
+    ];
+
+    #[test]
+    fn streamed_output_matches_rendered_output() {
+        let modes = [LinkMode::Inline, LinkMode::References, LinkMode::Hyperlink];
+        for (name, markdown) in CASES {
+            for link_mode in modes {
+                let renderer = renderer(link_mode);
+                let expected = renderer.render_to_string(markdown).expect("rendered");
+                for size in [1, 2, 7, 16, markdown.len()] {
+                    let (output, _) = stream(&renderer, markdown, size);
+                    assert_eq!(
+                        output, expected,
+                        "{} with {:?} links in chunks of {}",
+                        name, link_mode, size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_are_written_before_the_input_ends() {
+        let markdown = "First.\n\nSee [later].\n\nMiddle.\n\n[later]: https://later.test\n\nEnd.\n";
+        let renderer = renderer(LinkMode::Inline).color(false);
+        let (output, written) = stream(&renderer, markdown, 1);
+        assert!(output.contains("(https://later.test)"), "{}", output);
+
+        // Until the definition arrives, the block with the reference is held back.
+        let before_definition = &written[markdown.find("[later]:").expect("a definition")];
+        assert!(
+            before_definition.contains("First."),
+            "{}",
+            before_definition
+        );
+        assert!(!before_definition.contains("See"), "{}", before_definition);
+
+        // The last block waits for the end of the input.
+        let before_end = &written[markdown.len()];
+        assert!(before_end.contains("Middle."), "{}", before_end);
+        assert!(!before_end.contains("End."), "{}", before_end);
+    }
+}
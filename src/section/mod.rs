@@ -0,0 +1,313 @@
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag};
+
+use crate::error::{Error, Result};
+use crate::layout::heading_number;
+use crate::renderer;
+
+/// A top-level heading and the byte range of its section in the markdown source.
+struct Section {
+    level: usize,
+    text: String,
+    range: Range<usize>,
+}
+
+/// Finds the part of `markdown` under the heading matching `path`, a list of headings
+/// separated by `>` such as `Deployment > Rollback`. Each heading in the path matches
+/// case-insensitively on part of its text, exact matches winning over partial ones, and
+/// has to be nested in the section of the previous one. The section runs up to the next
+/// heading of the same or a higher level.
+pub(crate) fn find(markdown: &str, path: &str) -> Result<Range<usize>> {
+    let sections = sections(markdown);
+    let mut candidates: Vec<usize> = (0..sections.len()).collect();
+    let mut matches: Vec<usize> = vec![];
+
+    for (depth, segment) in path.split('>').map(str::trim).enumerate() {
+        if depth > 0 {
+            candidates = subsections(&sections, &matches);
+        }
+        let segment = segment.to_lowercase();
+        let exact: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|index| sections[*index].text.to_lowercase() == segment)
+            .collect();
+        matches = if exact.is_empty() {
+            candidates
+                .iter()
+                .copied()
+                .filter(|index| sections[*index].text.to_lowercase().contains(&segment))
+                .collect()
+        } else {
+            exact
+        };
+        if matches.is_empty() {
+            return Err(Error::UnknownSection {
+                section: path.to_owned(),
+                choices: candidates
+                    .iter()
+                    .map(|index| full_path(&sections, *index))
+                    .collect(),
+            });
+        }
+    }
+
+    match matches[..] {
+        [index] => Ok(sections[index].range.clone()),
+        _ => Err(Error::AmbiguousSection {
+            section: path.to_owned(),
+            matches: matches
+                .iter()
+                .map(|index| full_path(&sections, *index))
+                .collect(),
+        }),
+    }
+}
+
+/// The labels of the footnotes referenced in `range` of `markdown`, whose definitions belong
+/// with the section even when they are written outside of it.
+pub(crate) fn footnote_references(markdown: &str, range: &Range<usize>) -> Vec<String> {
+    Parser::new_ext(markdown, renderer::options())
+        .into_offset_iter()
+        .filter(|(_, offset)| range.contains(&offset.start))
+        .filter_map(|(event, _)| match event {
+            Event::FootnoteReference(label) => Some(label.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every heading outside of lists, quotes and other containers, with the range of its section.
+fn sections(markdown: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![];
+    let mut depth = 0;
+    let mut heading: Option<Section> = None;
+
+    for (event, range) in Parser::new_ext(markdown, renderer::options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(level, ..)) if depth == 0 => {
+                heading = Some(Section {
+                    level: heading_number(level),
+                    text: String::new(),
+                    range: range.start..markdown.len(),
+                });
+                depth += 1;
+            }
+            Event::End(Tag::Heading(..)) if depth == 1 => {
+                if let Some(mut heading) = heading.take() {
+                    heading.text = heading.text.trim().to_owned();
+                    for section in sections.iter_mut().rev() {
+                        if section.range.end == markdown.len() && section.level >= heading.level {
+                            section.range.end = heading.range.start;
+                        }
+                    }
+                    sections.push(heading);
+                }
+                depth -= 1;
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = &mut heading {
+                    heading.text.push_str(&text);
+                }
+            }
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => (),
+        }
+    }
+    sections
+}
+
+/// The headings nested in the sections of `parents`.
+fn subsections(sections: &[Section], parents: &[usize]) -> Vec<usize> {
+    (0..sections.len())
+        .filter(|index| {
+            parents.iter().any(|parent| {
+                index > parent && sections[*index].range.start < sections[*parent].range.end
+            })
+        })
+        .collect()
+}
+
+/// The heading at `index` preceded by the headings it is nested in, as in a section path.
+fn full_path(sections: &[Section], index: usize) -> String {
+    let mut path = vec![sections[index].text.as_str()];
+    let mut level = sections[index].level;
+    for section in sections[..index].iter().rev() {
+        if section.level < level {
+            path.push(&section.text);
+            level = section.level;
+        }
+    }
+    path.reverse();
+    path.join(" > ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKDOWN: &str = "\
+# Install
+
+## Linux
+
+On Linux.
+
+## Linux Mint
+
+## macOS
+
+## Notes
+
+# Usage
+
+## Options
+
+### Colors
+
+Colors.[^colors]
+
+## Notes
+
+See [^notes].
+
+> # Quoted
+
+# Deployment
+
+[^colors]: About colors.
+[^notes]: About notes.
+";
+
+    /// The section `path` finds, as its text.
+    fn find_text(path: &str) -> Result<&'static str> {
+        find(MARKDOWN, path).map(|range| &MARKDOWN[range])
+    }
+
+    fn strings(strings: &[&str]) -> Vec<String> {
+        strings.iter().map(|string| string.to_string()).collect()
+    }
+
+    #[test]
+    fn find_matches_headings_along_the_path() {
+        let cases = [
+            ("Install > Linux", "## Linux\n\nOn Linux.\n\n"),
+            ("linux", "## Linux\n\nOn Linux.\n\n"),
+            ("LINUX MINT", "## Linux Mint\n\n"),
+            ("mac", "## macOS\n\n"),
+            ("install>mac", "## macOS\n\n"),
+            (
+                "usage > notes",
+                "## Notes\n\nSee [^notes].\n\n> # Quoted\n\n",
+            ),
+            (
+                "Usage > Options > col",
+                "### Colors\n\nColors.[^colors]\n\n",
+            ),
+            (
+                "options",
+                "## Options\n\n### Colors\n\nColors.[^colors]\n\n",
+            ),
+            (
+                "deploy",
+                "# Deployment\n\n[^colors]: About colors.\n[^notes]: About notes.\n",
+            ),
+        ];
+        for (path, text) in cases {
+            match find_text(path) {
+                Ok(found) => assert_eq!(found, text, "section of {:?}", path),
+                Err(err) => panic!("no section for {:?}: {}", path, err),
+            }
+        }
+    }
+
+    #[test]
+    fn find_lists_the_choices_when_nothing_matches() {
+        let all = [
+            "Install",
+            "Install > Linux",
+            "Install > Linux Mint",
+            "Install > macOS",
+            "Install > Notes",
+            "Usage",
+            "Usage > Options",
+            "Usage > Options > Colors",
+            "Usage > Notes",
+            "Deployment",
+        ];
+        let cases: [(&str, &[&str]); 4] = [
+            ("quoted", &all),
+            ("windows", &all),
+            (
+                "usage > linux",
+                &[
+                    "Usage > Options",
+                    "Usage > Options > Colors",
+                    "Usage > Notes",
+                ],
+            ),
+            ("deployment > rollback", &[]),
+        ];
+        for (path, choices) in cases {
+            match find_text(path) {
+                Err(Error::UnknownSection {
+                    section,
+                    choices: found,
+                }) => {
+                    assert_eq!(section, path);
+                    assert_eq!(found, strings(choices), "choices for {:?}", path);
+                }
+                result => panic!("unexpected result for {:?}: {:?}", path, result),
+            }
+        }
+    }
+
+    #[test]
+    fn find_lists_the_matches_when_several_match() {
+        let cases: [(&str, &[&str]); 3] = [
+            ("lin", &["Install > Linux", "Install > Linux Mint"]),
+            ("notes", &["Install > Notes", "Usage > Notes"]),
+            (
+                "usage > o",
+                &[
+                    "Usage > Options",
+                    "Usage > Options > Colors",
+                    "Usage > Notes",
+                ],
+            ),
+        ];
+        for (path, matches) in cases {
+            match find_text(path) {
+                Err(Error::AmbiguousSection {
+                    section,
+                    matches: found,
+                }) => {
+                    assert_eq!(section, path);
+                    assert_eq!(found, strings(matches), "matches for {:?}", path);
+                }
+                result => panic!("unexpected result for {:?}: {:?}", path, result),
+            }
+        }
+    }
+
+    #[test]
+    fn footnote_references_are_found_in_the_section_only() {
+        let cases: [(&str, &[&str]); 4] = [
+            ("usage", &["colors", "notes"]),
+            ("colors", &["colors"]),
+            ("usage > notes", &["notes"]),
+            ("install", &[]),
+        ];
+        for (path, labels) in cases {
+            let range = find(MARKDOWN, path).expect("a section");
+            assert_eq!(
+                footnote_references(MARKDOWN, &range),
+                strings(labels),
+                "footnotes of {:?}",
+                path
+            );
+        }
+    }
+}
@@ -3,35 +3,30 @@ use crossterm::style::{Color, ContentStyle, Stylize};
 use super::Content;
 use crate::context::Context;
 
-pub(crate) struct BlockQuoteStyle {
+#[derive(Clone)]
+pub struct BlockQuoteStyle {
     style: ContentStyle,
     character: String,
 }
 
 impl BlockQuoteStyle {
-    fn new(style: ContentStyle, character: &str) -> Self {
+    pub fn new(style: ContentStyle, character: &str) -> Self {
         Self {
             style,
             character: character.to_owned(),
         }
     }
 
+    pub(crate) fn prefix(&self) -> Content {
+        Content::StyledContent(self.style.apply(format!("{} ", self.character)))
+    }
+
     pub(crate) fn get_styled_content(&self, text: &str, context: &Context) -> Vec<Content> {
         let mut style = self.style;
         for modifier in context.modifiers() {
             style = style.attribute(*modifier)
         }
-        if context.start_of_line().to_owned() {
-            vec![Content::StyledContent(style.apply(format!(
-                "{:width$}{character} {text}",
-                "",
-                width = context.indentation() * 2,
-                character = self.character,
-                text = text
-            )))]
-        } else {
-            vec![Content::StyledContent(style.apply(text.to_string()))]
-        }
+        vec![Content::StyledContent(style.apply(text.to_string()))]
     }
 }
 
@@ -1,89 +1,526 @@
-// use crossterm::style::{ContentStyle, Color, Stylize};
+use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+use std::{collections::HashMap, path::Path};
 use syntect::{
     easy::HighlightLines,
-    highlighting::{Theme, ThemeSet},
-    parsing::SyntaxSet,
-    util::as_24_bit_terminal_escaped,
+    highlighting::{FontStyle, Highlighter, Style, Theme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::context::Context;
+use crate::error::{Error, Result};
 
 use super::Content;
-// use super::Style;
 
-pub(crate) struct CodeBlockStyle {
+/// Code fence languages that are not the name or file extension of a syntax.
+const ALIASES: [(&str, &str); 14] = [
+    ("sh", "bash"),
+    ("shell", "bash"),
+    ("zsh", "bash"),
+    ("console", "bash"),
+    ("shell-session", "bash"),
+    ("golang", "go"),
+    ("c++", "cpp"),
+    ("objc", "objective-c"),
+    ("make", "makefile"),
+    ("jsonc", "json"),
+    ("text", "plain text"),
+    ("txt", "plain text"),
+    ("plain", "plain text"),
+    ("plaintext", "plain text"),
+];
+
+/// Space between the code and whatever is left of the block.
+const MARGIN: &str = "  ";
+
+/// Columns taken by the sides of a framed block and the space inside them.
+const FRAME_WIDTH: usize = 4;
+
+/// Goes between the line numbers and the code.
+const GUTTER_SEPARATOR: &str = " \u{2502} ";
+
+/// What happens to code lines wider than the block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LongLines {
+    /// The line goes on in the next rows, each starting with a continuation marker.
+    #[default]
+    Wrap,
+    /// The line is cut off, ending with an ellipsis.
+    Truncate,
+    /// The block is made as wide as the output, wrapping what still doesn't fit.
+    Widen,
+}
+
+pub struct CodeBlockStyle {
     syntax_set: SyntaxSet,
     theme: Theme,
     width: usize,
+    tab_width: usize,
+    long_lines: LongLines,
+    frame: bool,
+    continuation: String,
+    ellipsis: String,
+    aliases: HashMap<String, String>,
 }
 
 impl CodeBlockStyle {
-    fn new(width: usize, theme: &str) -> Self {
-        let theme_set = ThemeSet::load_defaults();
+    /// Uses `theme` as the name of a built-in syntect theme or as a path to a `.tmTheme` file.
+    pub fn new(width: usize, theme: &str) -> Result<Self> {
+        let mut theme_set = ThemeSet::load_defaults();
+        let theme = match theme_set.themes.remove(theme) {
+            Some(theme) => theme,
+            None if Path::new(theme).is_file() => ThemeSet::get_theme(theme)?,
+            None => return Err(Error::UnknownTheme(theme.to_owned())),
+        };
+
+        Ok(Self::with_theme(width, theme))
+    }
+
+    pub fn with_theme(width: usize, theme: Theme) -> Self {
         let syntax_set = SyntaxSet::load_defaults_newlines();
 
+        let aliases = ALIASES
+            .iter()
+            .map(|(alias, language)| (alias.to_string(), language.to_string()))
+            .collect();
+
         Self {
             syntax_set,
-            theme: theme_set.themes[theme].clone(),
+            theme,
             width,
+            tab_width: 4,
+            long_lines: LongLines::default(),
+            frame: false,
+            continuation: "\u{21aa} ".to_owned(),
+            ellipsis: "\u{2026}".to_owned(),
+            aliases,
         }
     }
 
-    pub(crate) fn syntax_set(&self) -> &SyntaxSet {
-        &self.syntax_set
+    /// Expands tabs to the next multiple of `tab_width` columns.
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width.max(1);
+        self
     }
 
-    pub(crate) fn width(&self) -> &usize {
-        &self.width
+    pub fn long_lines(mut self, long_lines: LongLines) -> Self {
+        self.long_lines = long_lines;
+        self
     }
 
-    pub(crate) fn highlight_lines(&self, syntax_name: &str) -> HighlightLines {
-        let syntax = self.syntax_set.find_syntax_by_name(syntax_name).unwrap();
-        HighlightLines::new(syntax, &self.theme)
+    /// What starts the rows a long line is wrapped onto.
+    pub fn continuation_glyph(mut self, glyph: &str) -> Self {
+        self.continuation = glyph.to_owned();
+        self
     }
 
-    pub(crate) fn get_styled_content(&self, text: &str, context: &Context) -> Vec<Content> {
-        let mut content: Vec<Content> = vec![];
-        for line in text.lines() {
-            let formatted_line = format!(
-                "{}{:>width$}\n",
-                line,
-                "",
-                width = (self.width() - line.len())
-            );
-            let ranges: Vec<(syntect::highlighting::Style, &str)> = self
-                .highlight_lines(context.code_block_syntax())
-                .highlight_line(&formatted_line, self.syntax_set())
-                .unwrap();
-            let escaped = as_24_bit_terminal_escaped(&ranges[..], true);
-            // stdout.queue_content(&format!(
-            //     "{:width$}",
-            //     "",
-            //     width = context.indentation() * 2
-            // ));
-            // stdout.queue_content(&escaped);
-            // stdout.reset_color();
-            content.push(Content::String(format!(
-                "{:width$}{content}",
-                "",
-                width = context.indentation() * 2,
-                content = escaped
-            )));
-        }
-        content
+    /// What ends a truncated line.
+    pub fn ellipsis_glyph(mut self, glyph: &str) -> Self {
+        self.ellipsis = glyph.to_owned();
+        self
+    }
+
+    /// Draws a rounded frame around each block, with its language in the top border.
+    pub fn frame(mut self, frame: bool) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    /// Highlights code blocks marked with `alias` as `language`, which is a syntax name,
+    /// a file extension or another alias.
+    pub fn alias(mut self, alias: &str, language: &str) -> Self {
+        self.aliases
+            .insert(alias.to_lowercase(), language.to_lowercase());
+        self
+    }
+
+    /// The syntax of a code fence language, matched through the aliases, then by file
+    /// extension and then by name, falling back to plain text.
+    pub(crate) fn find_syntax(&self, language: Option<&str>) -> &SyntaxReference {
+        let mut token = language.unwrap_or_default().to_lowercase();
+        // A few rounds let aliases point to other aliases without looping forever.
+        for _ in 0..4 {
+            match self.aliases.get(&token) {
+                Some(language) if *language != token => token = language.clone(),
+                _ => break,
+            }
+        }
+        if token.is_empty() {
+            return self.syntax_set.find_syntax_plain_text();
+        }
+        self.syntax_set
+            .find_syntax_by_token(&token)
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text())
+    }
+
+    pub(crate) fn highlight_lines(&self, language: Option<&str>) -> HighlightLines<'_> {
+        HighlightLines::new(self.find_syntax(language), &self.theme)
+    }
+
+    /// Highlights `text` and returns the content of each row of the block, which is at most
+    /// `max_width` columns wide, margin or frame included. The fence attributes of the block
+    /// can add a title, a line number gutter and a tint on highlighted lines.
+    pub(crate) fn get_styled_content(
+        &self,
+        text: &str,
+        context: &Context,
+        max_width: usize,
+    ) -> Result<Vec<Vec<Content>>> {
+        let info = context.code_block_info();
+        let lines: Vec<String> = text.lines().map(|line| self.expand_tabs(line)).collect();
+        let number_width = if info.line_numbers() {
+            lines.len().max(1).to_string().len()
+        } else {
+            0
+        };
+        let gutter_width = if info.line_numbers() {
+            number_width + GUTTER_SEPARATOR.width()
+        } else {
+            0
+        };
+
+        let max_width = if self.frame {
+            max_width.saturating_sub(FRAME_WIDTH)
+        } else {
+            max_width.saturating_sub(MARGIN.width())
+        };
+        let longest = lines.iter().map(|line| line.width()).max().unwrap_or(0) + gutter_width;
+        let width = match self.long_lines {
+            LongLines::Widen if longest > self.width => max_width,
+            _ => self.width.min(max_width),
+        };
+        // Wide enough for a double-width character after the continuation marker.
+        let code_width = width
+            .saturating_sub(gutter_width)
+            .max(self.continuation.width() + 2);
+
+        let highlighter = Highlighter::new(&self.theme);
+        let background = content_style(highlighter.get_default());
+        let tint = self.tint();
+        let gutter = match self.theme.settings.gutter_foreground {
+            Some(color) => background.with(rgb(color)),
+            None => background.attribute(Attribute::Dim),
+        };
+
+        let mut rows: Vec<Vec<Content>> = vec![];
+        let title = match info.title() {
+            Some(title) => Some(title),
+            None if self.frame || info.line_numbers() || info.has_highlighted_lines() => {
+                info.language()
+            }
+            None => None,
+        };
+        // A frame shows the title in its top border instead.
+        if !self.frame {
+            if let Some(title) = title {
+                let title_style = background.on(tint).attribute(Attribute::Bold);
+                let title = format!(" {} ", title);
+                rows.push(self.truncate(
+                    &[(title_style, &title)],
+                    gutter_width + code_width,
+                    title_style,
+                ));
+            }
+        }
+
+        let mut highlight_lines = self.highlight_lines(info.language());
+        for (index, line) in lines.iter().enumerate() {
+            let number = index + 1;
+            let (background, on) = if info.is_highlighted(number) {
+                (background.on(tint), Some(tint))
+            } else {
+                (background, None)
+            };
+            let line_with_end = format!("{}\n", line);
+            let ranges: Vec<(Style, &str)> =
+                highlight_lines.highlight_line(&line_with_end, &self.syntax_set)?;
+            let pieces: Vec<(ContentStyle, &str)> = ranges
+                .into_iter()
+                .map(|(style, text)| {
+                    let style = content_style(style);
+                    let style = match on {
+                        Some(color) => style.on(color),
+                        None => style,
+                    };
+                    (style, text.trim_end_matches('\n'))
+                })
+                .filter(|(_, text)| !text.is_empty())
+                .collect();
+
+            let code_rows = match self.long_lines {
+                LongLines::Truncate if line.width() > code_width => {
+                    vec![self.truncate(&pieces, code_width, background)]
+                }
+                _ => self.wrap(&pieces, code_width, background),
+            };
+            for (row_index, code_row) in code_rows.into_iter().enumerate() {
+                let mut row = vec![];
+                if info.line_numbers() {
+                    let number = match row_index {
+                        0 => format!("{:>1$}", number, number_width),
+                        _ => " ".repeat(number_width),
+                    };
+                    row.push(Content::StyledContent(
+                        gutter.apply(format!("{}{}", number, GUTTER_SEPARATOR)),
+                    ));
+                }
+                row.extend(code_row);
+                rows.push(row);
+            }
+        }
+
+        if self.frame {
+            return Ok(self.frame_rows(rows, title, gutter_width + code_width, background, gutter));
+        }
+        for row in &mut rows {
+            row.insert(0, Content::String(MARGIN.to_owned()));
+        }
+        Ok(rows)
+    }
+
+    /// Puts rows `width` columns wide in a rounded box, with `label` in its top border.
+    fn frame_rows(
+        &self,
+        rows: Vec<Vec<Content>>,
+        label: Option<&str>,
+        width: usize,
+        background: ContentStyle,
+        border: ContentStyle,
+    ) -> Vec<Vec<Content>> {
+        let label = match label {
+            Some(label) if label.width() + 2 <= width => format!(" {} ", label),
+            _ => String::new(),
+        };
+        let top = format!(
+            "\u{256d}\u{2500}{}{}\u{256e}",
+            label,
+            "\u{2500}".repeat(width + 1 - label.width())
+        );
+        let bottom = format!("\u{2570}{}\u{256f}", "\u{2500}".repeat(width + 2));
+
+        let mut framed = vec![vec![Content::StyledContent(border.apply(top))]];
+        for row in rows {
+            let mut framed_row = vec![
+                Content::StyledContent(border.apply("\u{2502}".to_owned())),
+                Content::StyledContent(background.apply(" ".to_owned())),
+            ];
+            framed_row.extend(row);
+            framed_row.push(Content::StyledContent(background.apply(" ".to_owned())));
+            framed_row.push(Content::StyledContent(border.apply("\u{2502}".to_owned())));
+            framed.push(framed_row);
+        }
+        framed.push(vec![Content::StyledContent(border.apply(bottom))]);
+        framed
+    }
+
+    /// The background of highlighted lines and of the title bar, the theme's line highlight
+    /// or else its background moved a little towards its foreground.
+    fn tint(&self) -> Color {
+        if let Some(color) = self.theme.settings.line_highlight {
+            return rgb(color);
+        }
+        let settings = &self.theme.settings;
+        let background = settings
+            .background
+            .unwrap_or(syntect::highlighting::Color::BLACK);
+        let foreground = settings
+            .foreground
+            .unwrap_or(syntect::highlighting::Color::WHITE);
+        let mix = |b: u8, f: u8| ((b as u16 * 5 + f as u16) / 6) as u8;
+        Color::Rgb {
+            r: mix(background.r, foreground.r),
+            g: mix(background.g, foreground.g),
+            b: mix(background.b, foreground.b),
+        }
+    }
+
+    fn expand_tabs(&self, line: &str) -> String {
+        let mut expanded = String::new();
+        let mut column = 0;
+        for c in line.chars() {
+            if c == '\t' {
+                let spaces = self.tab_width - column % self.tab_width;
+                expanded.push_str(&" ".repeat(spaces));
+                column += spaces;
+            } else {
+                expanded.push(c);
+                column += c.width().unwrap_or(0);
+            }
+        }
+        expanded
+    }
+
+    /// Splits a highlighted line into rows of `width` columns, starting each row after the
+    /// first with the continuation marker.
+    fn wrap(
+        &self,
+        pieces: &[(ContentStyle, &str)],
+        width: usize,
+        background: ContentStyle,
+    ) -> Vec<Vec<Content>> {
+        let mut rows = vec![];
+        let mut row = vec![];
+        let mut column = 0;
+        let mut row_start = 0;
+        for (style, text) in pieces {
+            let mut part = String::new();
+            for c in text.chars() {
+                let c_width = c.width().unwrap_or(0);
+                if column + c_width > width && column > row_start {
+                    push_part(&mut row, *style, &mut part);
+                    push_padding(&mut row, background, width.saturating_sub(column));
+                    rows.push(std::mem::replace(
+                        &mut row,
+                        vec![Content::StyledContent(
+                            background.apply(self.continuation.clone()),
+                        )],
+                    ));
+                    column = self.continuation.width();
+                    row_start = column;
+                }
+                part.push(c);
+                column += c_width;
+            }
+            push_part(&mut row, *style, &mut part);
+        }
+        push_padding(&mut row, background, width.saturating_sub(column));
+        rows.push(row);
+        rows
+    }
+
+    /// Cuts a highlighted line off to fit in `width` columns, ellipsis included.
+    fn truncate(
+        &self,
+        pieces: &[(ContentStyle, &str)],
+        width: usize,
+        background: ContentStyle,
+    ) -> Vec<Content> {
+        let ellipsis = &self.ellipsis;
+        let total: usize = pieces.iter().map(|(_, text)| text.width()).sum();
+        let limit = if total > width {
+            width.saturating_sub(ellipsis.width())
+        } else {
+            width
+        };
+        let mut row = vec![];
+        let mut column = 0;
+        'pieces: for (style, text) in pieces {
+            let mut part = String::new();
+            for c in text.chars() {
+                let c_width = c.width().unwrap_or(0);
+                if column + c_width > limit {
+                    push_part(&mut row, *style, &mut part);
+                    break 'pieces;
+                }
+                part.push(c);
+                column += c_width;
+            }
+            push_part(&mut row, *style, &mut part);
+        }
+        if total > width && !ellipsis.is_empty() {
+            row.push(Content::StyledContent(
+                background.apply(ellipsis.to_owned()),
+            ));
+            column += ellipsis.width();
+        }
+        push_padding(&mut row, background, width.saturating_sub(column));
+        row
+    }
+}
+
+fn push_part(row: &mut Vec<Content>, style: ContentStyle, part: &mut String) {
+    if !part.is_empty() {
+        row.push(Content::StyledContent(style.apply(std::mem::take(part))));
+    }
+}
+
+fn push_padding(row: &mut Vec<Content>, background: ContentStyle, width: usize) {
+    if width > 0 {
+        row.push(Content::StyledContent(background.apply(" ".repeat(width))));
     }
 }
 
-// impl Style for CodeBlockStyle {
-//     fn style(&self) -> ContentStyle {
-//         self.style
-//     }
-// }
+fn rgb(color: syntect::highlighting::Color) -> Color {
+    Color::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+fn content_style(style: Style) -> ContentStyle {
+    let mut content_style = ContentStyle::new()
+        .with(rgb(style.foreground))
+        .on(rgb(style.background));
+    if style.font_style.contains(FontStyle::BOLD) {
+        content_style = content_style.attribute(Attribute::Bold);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        content_style = content_style.attribute(Attribute::Italic);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        content_style = content_style.attribute(Attribute::Underlined);
+    }
+    content_style
+}
 
 impl Default for CodeBlockStyle {
     fn default() -> Self {
         let width = 80;
-        let theme = "base16-ocean.dark";
-        CodeBlockStyle::new(width, theme)
+        let theme = ThemeSet::load_defaults().themes["base16-ocean.dark"].clone();
+        CodeBlockStyle::with_theme(width, theme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_fits_wide_characters_in_narrow_blocks() {
+        let style = CodeBlockStyle::default();
+        let context = Context::default();
+        for max_width in 0..12 {
+            let rows = style
+                .get_styled_content("\u{6f22}\u{5b57}\u{6f22}\u{5b57}\tx\n", &context, max_width)
+                .unwrap();
+            let widths: Vec<usize> = rows
+                .iter()
+                .map(|row| row.iter().map(|content| content.text().width()).sum())
+                .collect();
+            assert!(
+                widths.iter().all(|width| *width == widths[0]),
+                "rows of different widths {:?} at {}",
+                widths,
+                max_width
+            );
+        }
+    }
+
+    #[test]
+    fn find_syntax_resolves_aliases_extensions_and_names() {
+        let style = CodeBlockStyle::default().alias("pl-sql", "sql");
+        let cases = [
+            (None, "Plain Text"),
+            (Some(""), "Plain Text"),
+            (Some("rust"), "Rust"),
+            (Some("rs"), "Rust"),
+            (Some("Rust"), "Rust"),
+            (Some("sh"), "Bourne Again Shell (bash)"),
+            (Some("console"), "Bourne Again Shell (bash)"),
+            (Some("c++"), "C++"),
+            (Some("js"), "JavaScript"),
+            (Some("text"), "Plain Text"),
+            (Some("pl-sql"), "SQL"),
+            (Some("no-such-language"), "Plain Text"),
+        ];
+        for (language, name) in cases {
+            assert_eq!(
+                style.find_syntax(language).name,
+                name,
+                "syntax of {:?}",
+                language
+            );
+        }
     }
 }
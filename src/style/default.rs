@@ -17,16 +17,7 @@ impl DefaultStyle {
         for modifier in context.modifiers() {
             style = style.attribute(*modifier)
         }
-        if context.start_of_line().to_owned() {
-            vec![Content::StyledContent(style.apply(format!(
-                "{:width$}{text}",
-                "",
-                width = context.indentation() * 2,
-                text = text
-            )))]
-        } else {
-            vec![Content::StyledContent(style.apply(text.to_string()))]
-        }
+        vec![Content::StyledContent(style.apply(text.to_string()))]
     }
 }
 
@@ -0,0 +1,245 @@
+/// An attribute following the language in a code fence info string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FenceAttribute {
+    /// A bare word such as `ignore` or `linenos`.
+    Flag(String),
+    /// A `key=value` pair, the value unquoted.
+    Value(String, String),
+    /// A list of line ranges such as `{3,5-7}`, without the braces.
+    Lines(String),
+}
+
+/// The info string of a fenced code block, such as `rust,ignore` or
+/// `rust title="src/main.rs" {3,5-7} linenos`, split into a language and its attributes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FenceInfo {
+    language: Option<String>,
+    attributes: Vec<FenceAttribute>,
+}
+
+impl FenceInfo {
+    pub fn parse(info: &str) -> Self {
+        let mut tokens = tokenize(info).into_iter().peekable();
+        let language = tokens
+            .next_if(|token| !token.starts_with('{') && !token.contains('='))
+            .map(|token| token.trim_start_matches('.').to_owned());
+        let attributes = tokens
+            .map(|token| {
+                if let Some(lines) = token.strip_prefix('{') {
+                    FenceAttribute::Lines(lines.trim_end_matches('}').to_owned())
+                } else if let Some((key, value)) = token.split_once('=') {
+                    FenceAttribute::Value(key.to_owned(), unquote(value).to_owned())
+                } else {
+                    FenceAttribute::Flag(token)
+                }
+            })
+            .collect();
+        Self {
+            language,
+            attributes,
+        }
+    }
+
+    /// The language token, the first word of the info string.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn attributes(&self) -> &[FenceAttribute] {
+        &self.attributes
+    }
+
+    /// The `title` value, usually the name of the file the code comes from.
+    pub fn title(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                FenceAttribute::Value(key, value) if key == "title" => Some(value.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Whether the `linenos` flag asks for a line number gutter.
+    pub fn line_numbers(&self) -> bool {
+        self.attributes.iter().any(|attribute| {
+            matches!(attribute, FenceAttribute::Flag(flag) if flag == "linenos" || flag == "linenums")
+        })
+    }
+
+    /// Whether any line ranges are given to highlight.
+    pub fn has_highlighted_lines(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| matches!(attribute, FenceAttribute::Lines(_)))
+    }
+
+    /// Whether line `number`, counted from 1, is in one of the highlighted ranges.
+    /// Ranges that are not numbers are ignored.
+    pub fn is_highlighted(&self, number: usize) -> bool {
+        self.attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                FenceAttribute::Lines(lines) => Some(lines.split(',')),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|range| {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                Some(start.trim().parse::<usize>().ok()?..=end.trim().parse::<usize>().ok()?)
+            })
+            .any(|range| range.contains(&number))
+    }
+}
+
+/// Splits an info string at whitespace and commas, keeping quoted values and braces whole.
+fn tokenize(info: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut closing: Option<char> = None;
+    for c in info.chars() {
+        match closing {
+            Some(close) if c == close => {
+                closing = None;
+                token.push(c);
+            }
+            Some(_) => token.push(c),
+            None if c.is_whitespace() || c == ',' => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            None => {
+                closing = match c {
+                    '"' => Some('"'),
+                    '\'' => Some('\''),
+                    '{' => Some('}'),
+                    _ => None,
+                };
+                token.push(c);
+            }
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(value) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return value;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(flag: &str) -> FenceAttribute {
+        FenceAttribute::Flag(flag.to_owned())
+    }
+
+    fn value(key: &str, value: &str) -> FenceAttribute {
+        FenceAttribute::Value(key.to_owned(), value.to_owned())
+    }
+
+    fn lines(lines: &str) -> FenceAttribute {
+        FenceAttribute::Lines(lines.to_owned())
+    }
+
+    #[test]
+    fn tokenize_splits_at_whitespace_and_commas() {
+        let cases: [(&str, &[&str]); 6] = [
+            ("", &[]),
+            ("rust", &["rust"]),
+            ("rust,ignore", &["rust", "ignore"]),
+            ("  rust ,  ignore  ", &["rust", "ignore"]),
+            ("rust title=\"a b\"", &["rust", "title=\"a b\""]),
+            ("rust {3, 5-7} x='c,d'", &["rust", "{3, 5-7}", "x='c,d'"]),
+        ];
+        for (info, tokens) in cases {
+            assert_eq!(tokenize(info), tokens, "tokens of {:?}", info);
+        }
+    }
+
+    #[test]
+    fn parse_splits_language_and_attributes() {
+        let cases: [(&str, Option<&str>, Vec<FenceAttribute>); 8] = [
+            ("", None, vec![]),
+            ("rust", Some("rust"), vec![]),
+            ("rust,ignore", Some("rust"), vec![flag("ignore")]),
+            (".python", Some("python"), vec![]),
+            (
+                "rust title=\"a b\"",
+                Some("rust"),
+                vec![value("title", "a b")],
+            ),
+            ("title='main.rs'", None, vec![value("title", "main.rs")]),
+            (
+                "{3,5-7} linenos",
+                None,
+                vec![lines("3,5-7"), flag("linenos")],
+            ),
+            (
+                "c++ {1} title=x linenums",
+                Some("c++"),
+                vec![lines("1"), value("title", "x"), flag("linenums")],
+            ),
+        ];
+        for (info, language, attributes) in cases {
+            let parsed = FenceInfo::parse(info);
+            assert_eq!(parsed.language(), language, "language of {:?}", info);
+            assert_eq!(parsed.attributes(), attributes, "attributes of {:?}", info);
+        }
+    }
+
+    #[test]
+    fn is_highlighted_matches_line_ranges() {
+        let cases: [(&str, &[usize]); 6] = [
+            ("rust", &[]),
+            ("rust {3}", &[3]),
+            ("rust {3,5-7}", &[3, 5, 6, 7]),
+            ("rust {1-2} {4}", &[1, 2, 4]),
+            ("rust { 2 - 3 }", &[2, 3]),
+            ("rust {x,2,3-y,5-4}", &[2]),
+        ];
+        for (info, highlighted) in cases {
+            let parsed = FenceInfo::parse(info);
+            let found: Vec<usize> = (0..10)
+                .filter(|number| parsed.is_highlighted(*number))
+                .collect();
+            assert_eq!(found, highlighted, "highlighted lines of {:?}", info);
+        }
+    }
+
+    #[test]
+    fn title_and_line_numbers_come_from_attributes() {
+        let cases = [
+            ("rust", None, false),
+            ("rust,ignore", None, false),
+            (
+                "rust title=\"src/main.rs\" linenos",
+                Some("src/main.rs"),
+                true,
+            ),
+            ("rust linenums title='a b'", Some("a b"), true),
+            ("rust titles=x linenos=1", None, false),
+        ];
+        for (info, title, line_numbers) in cases {
+            let parsed = FenceInfo::parse(info);
+            assert_eq!(parsed.title(), title, "title of {:?}", info);
+            assert_eq!(
+                parsed.line_numbers(),
+                line_numbers,
+                "line numbers of {:?}",
+                info
+            );
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::context::Context;
+
+use super::Content;
+use crossterm::style::{Color, ContentStyle, Stylize};
+
+#[derive(Clone)]
+pub struct FootnoteStyle {
+    style: ContentStyle,
+    rule: String,
+}
+
+impl FootnoteStyle {
+    pub fn new(style: ContentStyle, rule: &str) -> Self {
+        Self {
+            style,
+            rule: rule.to_owned(),
+        }
+    }
+
+    pub(crate) fn get_styled_content(&self, number: usize, context: &Context) -> Vec<Content> {
+        let mut style = self.style;
+        for modifier in context.modifiers() {
+            style = style.attribute(*modifier)
+        }
+        vec![Content::StyledContent(style.apply(format!("[{}]", number)))]
+    }
+
+    /// What starts the first line of a footnote definition.
+    pub(crate) fn marker(&self, number: usize) -> Content {
+        Content::StyledContent(self.style.apply(format!("[{}] ", number)))
+    }
+
+    /// The line that separates the document from its footnotes.
+    pub(crate) fn rule(&self) -> Content {
+        Content::StyledContent(self.style.apply(self.rule.clone()))
+    }
+}
+
+impl Default for FootnoteStyle {
+    fn default() -> Self {
+        let style = ContentStyle::new().with(Color::Cyan);
+        let rule = "\u{2500}".repeat(10);
+        FootnoteStyle::new(style, &rule)
+    }
+}
@@ -3,12 +3,13 @@ use crate::context::Context;
 use super::Content;
 use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
 
-pub(crate) struct HeadingStyle {
+#[derive(Clone)]
+pub struct HeadingStyle {
     style: ContentStyle,
 }
 
 impl HeadingStyle {
-    pub(crate) fn new(style: ContentStyle) -> Self {
+    pub fn new(style: ContentStyle) -> Self {
         Self { style }
     }
 
@@ -17,16 +18,7 @@ impl HeadingStyle {
         for modifier in context.modifiers() {
             style = style.attribute(*modifier)
         }
-        if context.start_of_line().to_owned() {
-            vec![Content::StyledContent(style.apply(format!(
-                "{:width$}{text}",
-                "",
-                width = context.indentation() * 2,
-                text = text
-            )))]
-        } else {
-            vec![Content::StyledContent(style.apply(text.to_string()))]
-        }
+        vec![Content::StyledContent(style.apply(text.to_string()))]
     }
 }
 
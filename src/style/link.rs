@@ -0,0 +1,82 @@
+use crate::context::Context;
+
+use super::Content;
+use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+
+/// How the target of a link is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkMode {
+    /// The link text is made clickable with OSC 8 escape sequences.
+    Hyperlink,
+    /// The link text is followed by its URL in parentheses.
+    #[default]
+    Inline,
+    /// The link text is followed by a number pointing into a list of
+    /// references at the end of the document.
+    References,
+}
+
+#[derive(Clone)]
+pub struct LinkStyle {
+    style: ContentStyle,
+    url_style: ContentStyle,
+}
+
+impl LinkStyle {
+    pub fn new(style: ContentStyle, url_style: ContentStyle) -> Self {
+        Self { style, url_style }
+    }
+
+    pub(crate) fn get_styled_content(&self, text: &str, context: &Context) -> Vec<Content> {
+        let mut style = self.style;
+        for modifier in context.modifiers() {
+            style = style.attribute(*modifier)
+        }
+        vec![Content::StyledContent(style.apply(text.to_string()))]
+    }
+
+    /// The URL shown after the link text when links are not clickable.
+    pub(crate) fn url(&self, url: &str) -> Vec<Content> {
+        vec![
+            Content::String(" ".to_string()),
+            Content::StyledContent(self.url_style.apply(format!("({})", url))),
+        ]
+    }
+
+    /// The number shown after the link text in [`LinkMode::References`].
+    pub(crate) fn reference(&self, number: usize) -> Vec<Content> {
+        vec![Content::StyledContent(
+            self.url_style.apply(format!("[{}]", number)),
+        )]
+    }
+
+    /// The title of the list of references.
+    pub(crate) fn references_title(&self) -> Vec<Content> {
+        vec![Content::StyledContent(
+            self.style.apply("References".to_string()),
+        )]
+    }
+
+    /// What starts the line of the reference numbered `number`.
+    pub(crate) fn reference_marker(&self, number: usize) -> Vec<Content> {
+        vec![Content::StyledContent(
+            self.url_style.apply(format!("[{}] ", number)),
+        )]
+    }
+
+    pub(crate) fn reference_url(&self, url: &str) -> Vec<Content> {
+        vec![Content::StyledContent(
+            self.url_style.apply(url.to_string()),
+        )]
+    }
+}
+
+impl Default for LinkStyle {
+    fn default() -> Self {
+        let style = ContentStyle::new()
+            .with(Color::Blue)
+            .attribute(Attribute::Underlined);
+        let url_style = ContentStyle::new().with(Color::DarkGrey);
+        LinkStyle::new(style, url_style)
+    }
+}
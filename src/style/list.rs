@@ -3,36 +3,31 @@ use crate::context::Context;
 use super::Content;
 use crossterm::style::{Color, ContentStyle, Stylize};
 
-pub(crate) struct UnorderedListStyle {
+#[derive(Clone)]
+pub struct UnorderedListStyle {
     style: ContentStyle,
     character: String,
 }
 
 impl UnorderedListStyle {
-    fn new(style: ContentStyle, character: &str) -> Self {
+    pub fn new(style: ContentStyle, character: &str) -> Self {
         Self {
             style,
             character: character.to_owned(),
         }
     }
 
+    pub(crate) fn marker(&self) -> Content {
+        Content::StyledContent(self.style.apply(format!("{} ", self.character)))
+    }
+
     pub(crate) fn get_styled_content(&self, text: &str, context: &Context) -> Vec<Content> {
-        let mut contents: Vec<Content> = vec![];
         let mut style = self.style;
-        if context.start_of_line().to_owned() {
-            contents.push(Content::StyledContent(style.apply(format!(
-                "\n{:width$}{character} ",
-                "",
-                width = context.indentation() * 2,
-                character = self.character
-            ))));
-        }
         for modifier in context.modifiers() {
             style = style.attribute(*modifier)
         }
 
-        contents.push(Content::StyledContent(style.apply(text.to_string())));
-        contents
+        vec![Content::StyledContent(style.apply(text.to_string()))]
     }
 }
 
@@ -46,42 +41,35 @@ impl Default for UnorderedListStyle {
     }
 }
 
-pub(crate) struct OrderedListStyle {
+#[derive(Clone)]
+pub struct OrderedListStyle {
     style: ContentStyle,
     character: String,
 }
 
 impl OrderedListStyle {
-    fn new(style: ContentStyle, character: &str) -> Self {
+    pub fn new(style: ContentStyle, character: &str) -> Self {
         Self {
             style,
             character: character.to_owned(),
         }
     }
 
-    pub(crate) fn get_styled_content(
-        &self,
-        text: &str,
-        context: &Context,
-        order: &u64,
-    ) -> Vec<Content> {
-        let mut contents: Vec<Content> = vec![];
+    pub(crate) fn marker(&self, order: &u64) -> Content {
+        Content::StyledContent(self.style.apply(format!(
+            "{order}{character} ",
+            order = order,
+            character = self.character
+        )))
+    }
+
+    pub(crate) fn get_styled_content(&self, text: &str, context: &Context) -> Vec<Content> {
         let mut style = self.style;
-        if context.start_of_line().to_owned() {
-            contents.push(Content::StyledContent(style.apply(format!(
-                "\n{:width$}{order}{character} ",
-                "",
-                order = order,
-                width = context.indentation() * 2,
-                character = self.character
-            ))));
-        }
         for modifier in context.modifiers() {
             style = style.attribute(*modifier)
         }
 
-        contents.push(Content::StyledContent(style.apply(text.to_string())));
-        contents
+        vec![Content::StyledContent(style.apply(text.to_string()))]
     }
 }
 
@@ -2,59 +2,127 @@ pub(crate) mod default;
 pub(crate) use default::DefaultStyle;
 
 pub(crate) mod paragraph;
-pub(crate) use paragraph::ParagraphStyle;
+pub use paragraph::ParagraphStyle;
 
 pub(crate) mod heading;
-pub(crate) use heading::HeadingStyle;
+pub use heading::HeadingStyle;
 
 pub(crate) mod block_quote;
-pub(crate) use block_quote::BlockQuoteStyle;
+pub use block_quote::BlockQuoteStyle;
 
 pub(crate) mod rule;
-pub(crate) use rule::RuleStyle;
+pub use rule::RuleStyle;
 
 pub(crate) mod list;
-pub(crate) use list::{OrderedListStyle, UnorderedListStyle};
+pub use list::{OrderedListStyle, UnorderedListStyle};
 
 pub(crate) mod code;
-pub(crate) use code::CodeStyle;
+pub use code::CodeStyle;
 
 pub(crate) mod code_block;
-pub(crate) use code_block::CodeBlockStyle;
+pub use code_block::{CodeBlockStyle, LongLines};
+
+pub(crate) mod fence;
+pub use fence::{FenceAttribute, FenceInfo};
+
+pub(crate) mod table;
+pub use table::TableStyle;
+
+pub(crate) mod task_list;
+pub use task_list::TaskListStyle;
+
+pub(crate) mod strikethrough;
+pub use strikethrough::StrikethroughStyle;
+
+pub(crate) mod footnote;
+pub use footnote::FootnoteStyle;
+
+pub(crate) mod link;
+pub use link::{LinkMode, LinkStyle};
 
 use crossterm::style::{Color, ContentStyle, StyledContent, Stylize};
+use pulldown_cmark::Tag;
+
+use crate::context::Context;
+use unicode_width::UnicodeWidthStr;
 
+#[derive(Clone, Debug)]
 pub(crate) enum Content {
     String(String),
     StyledContent(StyledContent<String>),
 }
 
-pub(crate) struct StyleSet {
+impl Content {
+    pub(crate) fn text(&self) -> &str {
+        match self {
+            Content::String(text) => text,
+            Content::StyledContent(content) => content.content(),
+        }
+    }
+
+    /// Splits the content into line breaks, runs of spaces and words, keeping its style.
+    pub(crate) fn split_words(&self) -> Vec<Content> {
+        let text = self.text();
+        let mut chunks: Vec<Content> = vec![];
+        let mut start = 0;
+        for (index, c) in text.char_indices().skip(1) {
+            let previous = text[..index].chars().last();
+            let boundary =
+                c == '\n' || previous == Some('\n') || (c == ' ') != (previous == Some(' '));
+            if boundary {
+                chunks.push(self.with_text(&text[start..index]));
+                start = index;
+            }
+        }
+        if start < text.len() {
+            chunks.push(self.with_text(&text[start..]));
+        }
+        chunks
+    }
+
+    pub(crate) fn with_text(&self, text: &str) -> Content {
+        match self {
+            Content::String(_) => Content::String(text.to_string()),
+            Content::StyledContent(content) => {
+                Content::StyledContent(content.style().apply(text.to_string()))
+            }
+        }
+    }
+}
+
+pub struct StyleSet {
     heading: Vec<HeadingStyle>,
 
     paragraph: ParagraphStyle,
 
     unordered_list: UnorderedListStyle,
     ordered_list: OrderedListStyle,
+    task_list: TaskListStyle,
     block_quote: BlockQuoteStyle,
 
     code: CodeStyle,
+    link: LinkStyle,
+    strikethrough: StrikethroughStyle,
 
     code_block: CodeBlockStyle,
 
+    table: TableStyle,
+    footnote: FootnoteStyle,
+
     rule: RuleStyle,
 
     default: DefaultStyle,
 }
 
 impl StyleSet {
+    /// The style of headings of `level`, counted from 1. Levels past the last style use it.
     pub(crate) fn heading(&self, level: usize) -> &HeadingStyle {
         let max_level = self.heading.len();
 
-        if level >= max_level {
+        if level > max_level {
             &self.heading[max_level - 1]
         } else {
-            &self.heading[level]
+            &self.heading[level - 1]
         }
     }
 
@@ -78,10 +146,26 @@ impl StyleSet {
         &self.code
     }
 
+    pub(crate) fn link(&self) -> &LinkStyle {
+        &self.link
+    }
+
+    pub(crate) fn strikethrough(&self) -> &StrikethroughStyle {
+        &self.strikethrough
+    }
+
     pub(crate) fn code_block(&self) -> &CodeBlockStyle {
         &self.code_block
     }
 
+    pub(crate) fn table(&self) -> &TableStyle {
+        &self.table
+    }
+
+    pub(crate) fn footnote(&self) -> &FootnoteStyle {
+        &self.footnote
+    }
+
     pub(crate) fn rule(&self) -> &RuleStyle {
         &self.rule
     }
@@ -89,100 +173,189 @@ impl StyleSet {
     pub(crate) fn default(&self) -> &DefaultStyle {
         &self.default
     }
+
+    /// Returns what starts a line inside the open blocks: quote bars and list markers, or
+    /// blanks as wide as the markers once they were printed or when `markers` is false.
+    pub(crate) fn prefix(&self, context: &Context, markers: bool) -> Vec<Content> {
+        let mut prefix: Vec<Content> = vec![];
+        let mut list = None;
+        for block in context.blocks() {
+            match block.tag() {
+                Tag::BlockQuote => prefix.push(self.block_quote.prefix()),
+                Tag::List(order) => list = Some(order),
+                Tag::Item | Tag::FootnoteDefinition(_) => {
+                    let marker = match (block.tag(), block.checked(), list) {
+                        (Tag::FootnoteDefinition(label), ..) => self
+                            .footnote
+                            .marker(context.find_footnote_number(label).unwrap_or(0)),
+                        (_, Some(checked), _) => self.task_list.marker(checked),
+                        (_, None, Some(Some(order))) => self.ordered_list.marker(order),
+                        _ => self.unordered_list.marker(),
+                    };
+                    if markers && block.marker_pending() {
+                        prefix.push(marker);
+                    } else {
+                        prefix.push(Content::String(" ".repeat(marker.text().width())));
+                    }
+                }
+                _ => (),
+            }
+        }
+        prefix
+    }
+
+    /// Returns the blank line that separates two blocks, keeping the quote bars around them.
+    pub(crate) fn separator(&self, context: &Context) -> Vec<Content> {
+        let mut separator = self.prefix(context, false);
+        while let Some(content) = separator.pop() {
+            let trimmed = content.text().trim_end();
+            if !trimmed.is_empty() {
+                separator.push(content.with_text(trimmed));
+                break;
+            }
+        }
+        separator
+    }
 }
 
-pub(crate) struct StyleSetBuilder {
+pub struct StyleSetBuilder {
     heading: Option<Vec<HeadingStyle>>,
 
     paragraph: Option<ParagraphStyle>,
 
     unordered_list: Option<UnorderedListStyle>,
     ordered_list: Option<OrderedListStyle>,
+    task_list: Option<TaskListStyle>,
     block_quote: Option<BlockQuoteStyle>,
 
     code: Option<CodeStyle>,
+    link: Option<LinkStyle>,
+    strikethrough: Option<StrikethroughStyle>,
 
     code_block: Option<CodeBlockStyle>,
 
+    table: Option<TableStyle>,
+    footnote: Option<FootnoteStyle>,
+
     rule: Option<RuleStyle>,
 }
 
 impl StyleSetBuilder {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             heading: None,
             paragraph: None,
             unordered_list: None,
             ordered_list: None,
+            task_list: None,
             block_quote: None,
             code: None,
+            link: None,
+            strikethrough: None,
             code_block: None,
+            table: None,
+            footnote: None,
             rule: None,
         }
     }
 
-    pub(crate) fn heading(mut self, heading: Vec<HeadingStyle>) -> Self {
+    /// Sets the styles of the heading levels from `h1` on, the last one also applying to the
+    /// deeper levels. Without any, the default heading styles are used.
+    pub fn heading(mut self, heading: Vec<HeadingStyle>) -> Self {
         self.heading = Some(heading);
         self
     }
 
-    pub(crate) fn paragraph(mut self, paragraph: ParagraphStyle) -> Self {
+    pub fn paragraph(mut self, paragraph: ParagraphStyle) -> Self {
         self.paragraph = Some(paragraph);
         self
     }
 
-    pub(crate) fn unordered_list(mut self, unordered_list: UnorderedListStyle) -> Self {
+    pub fn unordered_list(mut self, unordered_list: UnorderedListStyle) -> Self {
         self.unordered_list = Some(unordered_list);
         self
     }
 
-    pub(crate) fn ordered_list(mut self, ordered_list: OrderedListStyle) -> Self {
+    pub fn ordered_list(mut self, ordered_list: OrderedListStyle) -> Self {
         self.ordered_list = Some(ordered_list);
         self
     }
 
-    pub(crate) fn block_quote(mut self, block_quote: BlockQuoteStyle) -> Self {
+    pub fn task_list(mut self, task_list: TaskListStyle) -> Self {
+        self.task_list = Some(task_list);
+        self
+    }
+
+    pub fn block_quote(mut self, block_quote: BlockQuoteStyle) -> Self {
         self.block_quote = Some(block_quote);
         self
     }
 
-    pub(crate) fn code(mut self, code: CodeStyle) -> Self {
+    pub fn code(mut self, code: CodeStyle) -> Self {
         self.code = Some(code);
         self
     }
 
-    pub(crate) fn code_block(mut self, code_block: CodeBlockStyle) -> Self {
+    pub fn link(mut self, link: LinkStyle) -> Self {
+        self.link = Some(link);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: StrikethroughStyle) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn code_block(mut self, code_block: CodeBlockStyle) -> Self {
         self.code_block = Some(code_block);
         self
     }
 
-    pub(crate) fn rule(mut self, rule: RuleStyle) -> Self {
+    pub fn table(mut self, table: TableStyle) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    pub fn footnote(mut self, footnote: FootnoteStyle) -> Self {
+        self.footnote = Some(footnote);
+        self
+    }
+
+    pub fn rule(mut self, rule: RuleStyle) -> Self {
         self.rule = Some(rule);
         self
     }
 
-    pub(crate) fn build(self) -> StyleSet {
+    pub fn build(self) -> StyleSet {
         StyleSet {
-            heading: self.heading.unwrap_or_else(|| {
-                vec![
-                    HeadingStyle::new(ContentStyle::new().with(Color::Blue)),
-                    HeadingStyle::default(),
-                ]
-            }),
-            paragraph: self.paragraph.unwrap_or_else(|| ParagraphStyle::default()),
-            unordered_list: self
-                .unordered_list
-                .unwrap_or_else(|| UnorderedListStyle::default()),
-            ordered_list: self
-                .ordered_list
-                .unwrap_or_else(|| OrderedListStyle::default()),
-            block_quote: self
-                .block_quote
-                .unwrap_or_else(|| BlockQuoteStyle::default()),
-            code: self.code.unwrap_or_else(|| CodeStyle::default()),
-            code_block: self.code_block.unwrap_or_else(|| CodeBlockStyle::default()),
-            rule: self.rule.unwrap_or_else(|| RuleStyle::default()),
+            heading: self
+                .heading
+                .filter(|heading| !heading.is_empty())
+                .unwrap_or_else(|| {
+                    vec![
+                        HeadingStyle::new(ContentStyle::new().with(Color::Blue)),
+                        HeadingStyle::default(),
+                    ]
+                }),
+            paragraph: self.paragraph.unwrap_or_default(),
+            unordered_list: self.unordered_list.unwrap_or_default(),
+            ordered_list: self.ordered_list.unwrap_or_default(),
+            task_list: self.task_list.unwrap_or_default(),
+            block_quote: self.block_quote.unwrap_or_default(),
+            code: self.code.unwrap_or_default(),
+            link: self.link.unwrap_or_default(),
+            strikethrough: self.strikethrough.unwrap_or_default(),
+            code_block: self.code_block.unwrap_or_default(),
+            table: self.table.unwrap_or_default(),
+            footnote: self.footnote.unwrap_or_default(),
+            rule: self.rule.unwrap_or_default(),
             default: DefaultStyle::default(),
         }
     }
 }
+
+impl Default for StyleSetBuilder {
+    fn default() -> Self {
+        StyleSetBuilder::new()
+    }
+}
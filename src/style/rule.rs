@@ -1,30 +1,33 @@
 use super::Content;
 use crossterm::style::{Color, ContentStyle, Stylize};
+use unicode_width::UnicodeWidthStr;
 
-pub(crate) struct RuleStyle {
+#[derive(Clone)]
+pub struct RuleStyle {
     style: ContentStyle,
-    rule: String,
+    glyph: String,
 }
 
 impl RuleStyle {
-    fn new(style: ContentStyle, rule: &str) -> Self {
+    /// A rule made of `glyph` repeated across the line.
+    pub fn new(style: ContentStyle, glyph: &str) -> Self {
         Self {
             style,
-            rule: rule.to_owned(),
+            glyph: glyph.to_owned(),
         }
     }
 
-    pub(crate) fn get_styled_content(&self) -> Vec<Content> {
-        vec![Content::StyledContent(self.style.apply(self.rule.clone()))]
+    /// The rule filling `width` columns.
+    pub(crate) fn get_styled_content(&self, width: usize) -> Vec<Content> {
+        let count = width / self.glyph.width().max(1);
+        vec![Content::StyledContent(
+            self.style.apply(self.glyph.repeat(count)),
+        )]
     }
 }
 
 impl Default for RuleStyle {
     fn default() -> Self {
-        let mut style = ContentStyle::new();
-        style = style.with(Color::DarkGrey);
-
-        let rule = format!("  {:\u{2500}<1$}\n", "", 80);
-        RuleStyle::new(style, &rule)
+        RuleStyle::new(ContentStyle::new().with(Color::DarkGrey), "\u{2500}")
     }
 }
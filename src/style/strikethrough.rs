@@ -0,0 +1,22 @@
+use crossterm::style::Attribute;
+
+#[derive(Clone)]
+pub struct StrikethroughStyle {
+    attribute: Attribute,
+}
+
+impl StrikethroughStyle {
+    pub fn new(attribute: Attribute) -> Self {
+        Self { attribute }
+    }
+
+    pub(crate) fn attribute(&self) -> Attribute {
+        self.attribute
+    }
+}
+
+impl Default for StrikethroughStyle {
+    fn default() -> Self {
+        StrikethroughStyle::new(Attribute::CrossedOut)
+    }
+}
@@ -0,0 +1,150 @@
+use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+use pulldown_cmark::Alignment;
+use unicode_width::UnicodeWidthStr;
+
+use super::Content;
+use crate::context::{Context, Table};
+use crate::layout::{LayoutBuilder, Line};
+
+const MIN_COLUMN_WIDTH: usize = 3;
+
+#[derive(Clone)]
+pub struct TableStyle {
+    border: ContentStyle,
+    header: ContentStyle,
+    cell: ContentStyle,
+}
+
+impl TableStyle {
+    pub fn new(border: ContentStyle, header: ContentStyle, cell: ContentStyle) -> Self {
+        Self {
+            border,
+            header,
+            cell,
+        }
+    }
+
+    pub(crate) fn get_styled_content(
+        &self,
+        text: &str,
+        context: &Context,
+        header: bool,
+    ) -> Vec<Content> {
+        let mut style = if header { self.header } else { self.cell };
+        for modifier in context.modifiers() {
+            style = style.attribute(*modifier)
+        }
+        vec![Content::StyledContent(style.apply(text.to_string()))]
+    }
+
+    /// Draws `table` as a grid no wider than `width`, wrapping cells when it has to.
+    pub(crate) fn get_table_content(&self, table: &Table, width: usize) -> Vec<Vec<Content>> {
+        let columns = table.rows().iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return vec![];
+        }
+
+        let mut widths = vec![0; columns];
+        for row in table.rows() {
+            for (column, cell) in row.iter().enumerate() {
+                let cell_width = cell.iter().map(|content| content.text().width()).sum();
+                widths[column] = widths[column].max(cell_width);
+            }
+        }
+        let available = width.saturating_sub(3 * columns + 1);
+        while widths.iter().sum::<usize>() > available {
+            let (widest, column_width) = widths
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, width)| **width)
+                .map(|(column, width)| (column, *width))
+                .unwrap_or((0, 0));
+            if column_width <= MIN_COLUMN_WIDTH {
+                break;
+            }
+            widths[widest] -= 1;
+        }
+
+        let mut lines = vec![self.border_line(&widths, '\u{250c}', '\u{252c}', '\u{2510}')];
+        for (index, row) in table.rows().iter().enumerate() {
+            if index > 0 && index == *table.head_rows() {
+                lines.push(self.border_line(&widths, '\u{251c}', '\u{253c}', '\u{2524}'));
+            }
+            let cells: Vec<Vec<Line>> = (0..columns)
+                .map(|column| match row.get(column) {
+                    Some(cell) => wrap_cell(cell, widths[column]),
+                    None => vec![],
+                })
+                .collect();
+            let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            for line_index in 0..height {
+                let mut line = vec![self.border_content("\u{2502}")];
+                for (column, cell) in cells.iter().enumerate() {
+                    line.push(Content::String(" ".to_string()));
+                    let alignment = table
+                        .alignments()
+                        .get(column)
+                        .copied()
+                        .unwrap_or(Alignment::None);
+                    line.extend(align(cell.get(line_index), widths[column], alignment));
+                    line.push(Content::String(" ".to_string()));
+                    line.push(self.border_content("\u{2502}"));
+                }
+                lines.push(line);
+            }
+        }
+        lines.push(self.border_line(&widths, '\u{2514}', '\u{2534}', '\u{2518}'));
+        lines
+    }
+
+    fn border_content(&self, text: &str) -> Content {
+        Content::StyledContent(self.border.apply(text.to_string()))
+    }
+
+    fn border_line(&self, widths: &[usize], left: char, middle: char, right: char) -> Vec<Content> {
+        let segments: Vec<String> = widths
+            .iter()
+            .map(|width| "\u{2500}".repeat(width + 2))
+            .collect();
+        let line = format!("{}{}{}", left, segments.join(&middle.to_string()), right);
+        vec![self.border_content(&line)]
+    }
+}
+
+fn wrap_cell(cell: &[Content], width: usize) -> Vec<Line> {
+    let mut layout = LayoutBuilder::new(width);
+    layout.push_wrapped_content(cell.to_vec(), vec![]);
+    layout.build().lines().to_vec()
+}
+
+fn align(line: Option<&Line>, width: usize, alignment: Alignment) -> Vec<Content> {
+    let mut contents: Vec<Content> = vec![];
+    let line_width = line.map_or(0, Line::width);
+    let padding = width.saturating_sub(line_width);
+    let (left, right) = match alignment {
+        Alignment::Right => (padding, 0),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::None | Alignment::Left => (0, padding),
+    };
+    contents.push(Content::String(" ".repeat(left)));
+    if let Some(line) = line {
+        for span in line.spans() {
+            contents.push(Content::StyledContent(
+                span.style().apply(span.text().to_string()),
+            ));
+        }
+    }
+    contents.push(Content::String(" ".repeat(right)));
+    contents
+}
+
+impl Default for TableStyle {
+    fn default() -> Self {
+        let border = ContentStyle::new().with(Color::DarkGrey);
+        let header = ContentStyle::new()
+            .with(Color::Yellow)
+            .attribute(Attribute::Bold);
+        let cell = ContentStyle::new().with(Color::White);
+        TableStyle::new(border, header, cell)
+    }
+}
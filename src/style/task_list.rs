@@ -0,0 +1,42 @@
+use super::Content;
+use crossterm::style::{Color, ContentStyle, Stylize};
+
+#[derive(Clone)]
+pub struct TaskListStyle {
+    style: ContentStyle,
+    checked_style: ContentStyle,
+    unchecked: String,
+    checked: String,
+}
+
+impl TaskListStyle {
+    pub fn new(
+        style: ContentStyle,
+        checked_style: ContentStyle,
+        unchecked: &str,
+        checked: &str,
+    ) -> Self {
+        Self {
+            style,
+            checked_style,
+            unchecked: unchecked.to_owned(),
+            checked: checked.to_owned(),
+        }
+    }
+
+    pub(crate) fn marker(&self, checked: bool) -> Content {
+        if checked {
+            Content::StyledContent(self.checked_style.apply(format!("{} ", self.checked)))
+        } else {
+            Content::StyledContent(self.style.apply(format!("{} ", self.unchecked)))
+        }
+    }
+}
+
+impl Default for TaskListStyle {
+    fn default() -> Self {
+        let style = ContentStyle::new().with(Color::White);
+        let checked_style = ContentStyle::new().with(Color::Green);
+        TaskListStyle::new(style, checked_style, "\u{2610}", "\u{2611}")
+    }
+}
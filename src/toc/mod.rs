@@ -0,0 +1,95 @@
+use std::fmt;
+
+use crate::layout::Heading;
+
+/// A heading numbered by its position in the outline, such as `2.1.`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TocEntry {
+    number: String,
+    depth: usize,
+    text: String,
+    line: usize,
+}
+
+impl TocEntry {
+    pub fn number(&self) -> &str {
+        &self.number
+    }
+
+    /// How deep the heading is nested in the outline, from 0 for the outermost headings.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The index of the heading's first line in the rendered document.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+/// The outline of a document's headings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableOfContents {
+    entries: Vec<TocEntry>,
+}
+
+impl TableOfContents {
+    /// Numbers `headings`, nesting each one under the closest previous heading of a
+    /// higher level, so that skipped levels don't leave gaps in the outline.
+    pub fn new(headings: &[Heading]) -> Self {
+        let mut open: Vec<(usize, usize)> = vec![];
+        let entries = headings
+            .iter()
+            .map(|heading| {
+                while open
+                    .last()
+                    .is_some_and(|(level, _)| *level > heading.level())
+                {
+                    open.pop();
+                }
+                match open.last_mut() {
+                    Some((level, count)) if *level == heading.level() => *count += 1,
+                    _ => open.push((heading.level(), 1)),
+                }
+                let number: String = open
+                    .iter()
+                    .map(|(_, count)| format!("{}.", count))
+                    .collect();
+                TocEntry {
+                    number,
+                    depth: open.len() - 1,
+                    text: heading.text().to_owned(),
+                    line: heading.line(),
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[TocEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl fmt::Display for TableOfContents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{}{} {}",
+                "   ".repeat(entry.depth),
+                entry.number,
+                entry.text
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often watched files are checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long files have to stay unchanged before a change is reported, so that an editor
+/// writing a file in several steps causes a single re-render.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// The modification time and size of a file, `None` while it can't be read.
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Polls files for changes by comparing their modification times and sizes.
+pub struct Watcher {
+    files: Vec<(PathBuf, Stamp)>,
+    changed_at: Option<Instant>,
+}
+
+impl Watcher {
+    pub fn new<P: AsRef<Path>>(paths: &[P]) -> Self {
+        let files = paths
+            .iter()
+            .map(|path| (path.as_ref().to_owned(), stamp(path.as_ref())))
+            .collect();
+        Self {
+            files,
+            changed_at: None,
+        }
+    }
+
+    /// Whether a file changed since the last reported change and then stayed unchanged
+    /// for a while.
+    pub fn changed(&mut self) -> bool {
+        let now = Instant::now();
+        for (path, last) in &mut self.files {
+            let current = stamp(path);
+            if current != *last {
+                *last = current;
+                self.changed_at = Some(now);
+            }
+        }
+        match self.changed_at {
+            Some(changed_at) if now.duration_since(changed_at) >= DEBOUNCE => {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Blocks until [`Watcher::changed`] reports a change.
+    pub fn wait(&mut self) {
+        while !self.changed() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
@@ -0,0 +1,50 @@
+use crossterm::style::Color;
+use dear_md::{Document, Renderer, StyleSetBuilder};
+
+fn render(markdown: &str) -> Document {
+    Renderer::new(StyleSetBuilder::new().build())
+        .width(80)
+        .render_document(markdown)
+        .unwrap()
+}
+
+/// The foreground color of the span holding `text`.
+fn foreground(document: &Document, text: &str) -> Option<Color> {
+    document
+        .lines()
+        .iter()
+        .flat_map(|line| line.spans())
+        .find(|span| span.text().contains(text))
+        .and_then(|span| span.style().foreground_color)
+}
+
+#[test]
+fn block_comment_keeps_its_style_across_lines() {
+    let document = render(
+        "```rust\n/* opening line\n   middle line\n   closing line */\nlet value = 1;\n```\n",
+    );
+
+    let comment = foreground(&document, "opening line");
+    assert!(comment.is_some());
+    assert_eq!(foreground(&document, "middle line"), comment);
+    assert_eq!(foreground(&document, "closing line"), comment);
+    assert_ne!(foreground(&document, "let"), comment);
+}
+
+#[test]
+fn multi_line_string_keeps_its_style_across_lines() {
+    let document = render("```python\ntext = \"\"\"first\nsecond\n\"\"\"\nnumber = 1\n```\n");
+
+    let string = foreground(&document, "first");
+    assert!(string.is_some());
+    assert_eq!(foreground(&document, "second"), string);
+    assert_ne!(foreground(&document, "number"), string);
+}
+
+#[test]
+fn unterminated_comment_does_not_leak_into_the_next_code_block() {
+    let document = render("```rust\n/* never closed\n```\n\n```rust\nlet value = 1;\n```\n");
+
+    let comment = foreground(&document, "never closed");
+    assert_ne!(foreground(&document, "let"), comment);
+}